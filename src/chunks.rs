@@ -0,0 +1,327 @@
+#![allow(clippy::type_complexity)]
+
+use std::collections::{HashMap, HashSet};
+
+use bevy::{
+    prelude::*,
+    render::{mesh::Indices, primitives::Aabb, render_resource::PrimitiveTopology},
+};
+
+use crate::{
+    elevation::Elevation,
+    prelude::*,
+    terrain::Terrain,
+    tiles::{index_tiles, Tile, TileIndex},
+};
+
+pub struct ChunksPlugin;
+
+impl Plugin for ChunksPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Chunks>()
+            .add_systems(OnExit(AppState::Loading), spawn_chunk_material)
+            .add_systems(OnExit(AppState::InGame), despawn_chunks)
+            .add_systems(
+                Update,
+                (mark_changed_chunks, rebuild_chunks)
+                    .chain()
+                    .after(index_tiles)
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+/// How many steps from its center tile a chunk reaches, so that each chunk
+/// holds `3 * r * r + 3 * r + 1` tiles.
+pub const CHUNK_RADIUS: u32 = 4;
+
+/// How much smaller than the grid cell each tile is drawn, leaving a small gap
+/// between tiles so that their outlines can be seen.
+pub const TILE_GAP_SCALE: f32 = 0.98;
+
+/// The coordinate of a chunk of tiles.
+///
+/// Chunks are hexagons of all tiles within `CHUNK_RADIUS` steps of a center
+/// tile. They cover the grid without gaps or overlaps just like tiles do, so
+/// chunk coordinates form a coarser hex grid of their own.
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChunkCoord(pub HexCoord);
+
+impl ChunkCoord {
+    /// The steps from the center of a chunk to the centers of the chunks at
+    /// `SE` and `S` of it in chunk coordinates.
+    const SE_CENTER: HexCoord = HexCoord::new(CHUNK_RADIUS as i32 + 1, CHUNK_RADIUS as i32);
+    const S_CENTER: HexCoord = HexCoord::new(-(CHUNK_RADIUS as i32), 2 * CHUNK_RADIUS as i32 + 1);
+
+    /// The chunk containing the given tile.
+    pub fn from_tile(tile: HexCoord) -> Self {
+        // Solve `tile = SE_CENTER * q + S_CENTER * r` for fractional q and r,
+        // then round to the nearest chunk. Rounding in chunk space doesn't
+        // quite follow the jagged chunk edges, so the neighbors of the rounded
+        // chunk are checked as well
+        let radius = CHUNK_RADIUS as f32;
+        let tiles_per_chunk = 3. * radius * radius + 3. * radius + 1.;
+        let (q, r) = (tile.q as f32, tile.r as f32);
+        let rounded = HexCoord::from(TilePosition::new(
+            ((2. * radius + 1.) * q + radius * r) / tiles_per_chunk,
+            ((radius + 1.) * r - radius * q) / tiles_per_chunk,
+        ));
+
+        std::iter::once(rounded)
+            .chain(rounded.neighbors())
+            .map(Self)
+            .find(|chunk| chunk.center().distance(tile) <= CHUNK_RADIUS as i32)
+            .expect("every tile belongs to one of the nearest chunks")
+    }
+
+    /// The tile at the center of the chunk.
+    pub fn center(self) -> HexCoord {
+        Self::SE_CENTER * self.0.q + Self::S_CENTER * self.0.r
+    }
+
+    /// The coordinates of all tiles in the chunk.
+    pub fn tiles(self) -> impl Iterator<Item = HexCoord> {
+        self.center().range(CHUNK_RADIUS)
+    }
+}
+
+/// The chunk entities drawing the tiles, and which chunks need to be rebuilt
+/// because tiles in them have changed.
+#[derive(Resource, Default)]
+struct Chunks {
+    entities: HashMap<ChunkCoord, Entity>,
+    /// The chunk each tile entity was last drawn in, so that chunks can be
+    /// rebuilt when tiles move or are despawned.
+    tile_chunks: HashMap<Entity, ChunkCoord>,
+    dirty: HashSet<ChunkCoord>,
+    material: Option<Handle<StandardMaterial>>,
+}
+
+/// Builds a single mesh out of any number of hexagonal prisms, colored with
+/// vertex colors.
+#[derive(Default)]
+pub struct HexMeshBuilder {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    colors: Vec<[f32; 4]>,
+    indices: Vec<u32>,
+}
+
+impl HexMeshBuilder {
+    /// Add a hexagonal prism standing on the ground at `center`, with its top
+    /// face at `height` and its corners at `corners`, relative to `center`.
+    /// Only the top face is added if `height` is zero.
+    pub fn add_prism(&mut self, center: Vec2, corners: &[Vec2; 6], height: f32, color: Color) {
+        let to_vec3 = |corner: Vec2, y: f32| [center.x + corner.x, y, -center.y - corner.y];
+        let color = color.as_linear_rgba_f32();
+
+        let top_index = self.positions.len() as u32;
+        self.positions.push(to_vec3(Vec2::ZERO, height));
+        self.normals.push([0., 1., 0.]);
+        self.uvs.push([0.5, 0.5]);
+        for (i, corner) in corners.iter().enumerate() {
+            let direction = corner.normalize_or_zero();
+            self.positions.push(to_vec3(*corner, height));
+            self.normals.push([0., 1., 0.]);
+            self.uvs
+                .push([0.5 + direction.x / 2., 0.5 - direction.y / 2.]);
+            self.indices.extend([
+                top_index,
+                top_index + i as u32 + 1,
+                top_index + (i as u32 + 1) % 6 + 1,
+            ]);
+        }
+        self.colors.extend([color; 7]);
+
+        if height <= 0. {
+            return;
+        }
+
+        for i in 0..6 {
+            let (start, end) = (corners[i], corners[(i + 1) % 6]);
+            let normal = (start + end).normalize_or_zero();
+            let first_index = self.positions.len() as u32;
+            self.positions.extend([
+                to_vec3(start, height),
+                to_vec3(start, 0.),
+                to_vec3(end, 0.),
+                to_vec3(end, height),
+            ]);
+            self.normals.extend([[normal.x, 0., -normal.y]; 4]);
+            self.uvs.extend([[0., 0.], [0., 1.], [1., 1.], [1., 0.]]);
+            self.colors.extend([color; 4]);
+            self.indices
+                .extend([0, 1, 2, 0, 2, 3].map(|offset| first_index + offset));
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    pub fn build(self) -> Mesh {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, self.positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, self.uvs);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, self.colors);
+        mesh.set_indices(Some(Indices::U32(self.indices)));
+        mesh
+    }
+}
+
+/// The corners of a drawn tile relative to its center, shrunk by
+/// `TILE_GAP_SCALE`.
+pub fn tile_corners(layout: &HexLayout) -> [Vec2; 6] {
+    let corners: Vec<_> = layout
+        .corners(&TilePosition::ZERO)
+        .map(|corner| (corner - layout.origin) * TILE_GAP_SCALE)
+        .collect();
+    corners.try_into().expect("hexagons have six corners")
+}
+
+fn spawn_chunk_material(
+    mut chunks: ResMut<Chunks>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    // The terrain colors come from the vertex colors of the chunk meshes
+    chunks.material = Some(materials.add(StandardMaterial {
+        base_color: Color::WHITE,
+        ..default()
+    }));
+}
+
+fn despawn_chunks(mut commands: Commands, mut chunks: ResMut<Chunks>) {
+    for entity in chunks.entities.values() {
+        commands.entity(*entity).despawn_recursive();
+    }
+
+    *chunks = Chunks::default();
+}
+
+/// Marks the chunks of tiles that have been spawned, despawned or changed in
+/// a way that affects how they're drawn.
+fn mark_changed_chunks(
+    changed_query: Query<
+        (Entity, &HexCoord),
+        (
+            With<Tile>,
+            Or<(Changed<HexCoord>, Changed<Terrain>, Changed<Elevation>)>,
+        ),
+    >,
+    mut removed_tiles: RemovedComponents<Tile>,
+    mut chunks: ResMut<Chunks>,
+) {
+    let chunks = chunks.as_mut();
+    for entity in removed_tiles.read() {
+        if let Some(chunk) = chunks.tile_chunks.remove(&entity) {
+            chunks.dirty.insert(chunk);
+        }
+    }
+
+    for (entity, coord) in changed_query.iter() {
+        let chunk = ChunkCoord::from_tile(*coord);
+        if let Some(previous) = chunks.tile_chunks.insert(entity, chunk) {
+            chunks.dirty.insert(previous);
+        }
+        chunks.dirty.insert(chunk);
+    }
+}
+
+/// Rebuilds the merged mesh of every chunk marked as changed, spawning and
+/// despawning chunk entities as needed.
+fn rebuild_chunks(
+    mut commands: Commands,
+    mut chunks: ResMut<Chunks>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    tile_query: Query<(&Terrain, Option<&Elevation>), With<Tile>>,
+    tile_index: Res<TileIndex>,
+    layout: Res<HexLayout>,
+) {
+    if chunks.dirty.is_empty() {
+        return;
+    }
+    let Some(material) = chunks.material.clone() else {
+        warn!("Missing chunk material handle in resource");
+        return;
+    };
+
+    let corners = tile_corners(&layout);
+    let chunks = chunks.as_mut();
+    for chunk in chunks.dirty.drain() {
+        let chunk_center = layout.hex_to_world(chunk.center());
+        let mut builder = HexMeshBuilder::default();
+        for coord in chunk.tiles() {
+            let Some((terrain, elevation)) = tile_index
+                .get(coord)
+                .and_then(|entity| tile_query.get(entity).ok())
+            else {
+                continue;
+            };
+            let height = elevation.copied().unwrap_or_default().height();
+            let center = layout.hex_to_world(coord) - chunk_center;
+            builder.add_prism(center, &corners, height, terrain.color());
+        }
+
+        if builder.is_empty() {
+            if let Some(entity) = chunks.entities.remove(&chunk) {
+                commands.entity(entity).despawn_recursive();
+            }
+            continue;
+        }
+
+        let mesh = meshes.add(builder.build());
+        if let Some(entity) = chunks.entities.get(&chunk) {
+            // Bounds are only calculated for meshes without them, so remove
+            // the stale bounds of the previous mesh
+            commands.entity(*entity).insert(mesh).remove::<Aabb>();
+        } else {
+            let entity = commands
+                .spawn((
+                    PbrBundle {
+                        mesh,
+                        material: material.clone(),
+                        transform: Transform::from_translation(
+                            layout.hex_to_vec3_with_y(chunk.center(), 0.),
+                        ),
+                        ..default()
+                    },
+                    chunk,
+                ))
+                .id();
+            chunks.entities.insert(chunk, entity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_tile_belongs_to_exactly_one_chunk() {
+        for tile in HexCoord::ZERO.range(60) {
+            let chunk = ChunkCoord::from_tile(tile);
+            assert!(chunk.tiles().any(|chunk_tile| chunk_tile == tile));
+
+            let containing = chunk
+                .0
+                .range(2)
+                .map(ChunkCoord)
+                .filter(|other| other.center().distance(tile) <= CHUNK_RADIUS as i32)
+                .count();
+            assert_eq!(containing, 1, "{tile:?} is in {containing} chunks");
+        }
+    }
+
+    #[test]
+    fn chunks_hold_a_hexagon_of_tiles() {
+        let radius = CHUNK_RADIUS as usize;
+        let chunk = ChunkCoord(HexCoord::new(-2, 1));
+        assert_eq!(chunk.tiles().count(), 3 * radius * radius + 3 * radius + 1);
+        assert!(chunk
+            .tiles()
+            .all(|tile| ChunkCoord::from_tile(tile) == chunk));
+    }
+}
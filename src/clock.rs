@@ -0,0 +1,30 @@
+use std::time::Duration;
+
+use crate::prelude::*;
+use bevy::prelude::*;
+
+pub struct ClockPlugin;
+
+impl Plugin for ClockPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameClock>()
+            .add_systems(Update, tick_game_clock.run_if(in_state(AppState::InGame)))
+            .add_systems(OnExit(AppState::InGame), reset_game_clock);
+    }
+}
+
+/// How long the current game has been played. Unlike `Time`, this is saved
+/// along with the game, so it keeps counting from where it was when a save is
+/// loaded.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq)]
+pub struct GameClock {
+    pub elapsed: Duration,
+}
+
+fn tick_game_clock(mut clock: ResMut<GameClock>, time: Res<Time>) {
+    clock.elapsed += time.delta();
+}
+
+fn reset_game_clock(mut clock: ResMut<GameClock>) {
+    *clock = GameClock::default();
+}
@@ -0,0 +1,102 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{prelude::*, tiles::TileIndex};
+
+/// How many steps a tile is raised above the ground.
+#[derive(
+    Component,
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+)]
+#[serde(transparent)]
+pub struct Elevation(pub u32);
+
+impl Elevation {
+    /// The world space height of one elevation step.
+    pub const STEP_HEIGHT: f32 = 0.08;
+
+    /// The world space height of the top face of a tile at this elevation.
+    pub fn height(self) -> f32 {
+        self.0 as f32 * Self::STEP_HEIGHT
+    }
+}
+
+/// Looks up the height of the top face of tiles by their coordinate.
+#[derive(SystemParam)]
+pub struct TileHeights<'w, 's> {
+    tile_index: Res<'w, TileIndex>,
+    elevation_query: Query<'w, 's, &'static Elevation>,
+}
+
+impl<'w, 's> TileHeights<'w, 's> {
+    /// The height of the top face of the tile at the given coordinate, or
+    /// `None` if there is no tile there.
+    pub fn get(&self, coord: HexCoord) -> Option<f32> {
+        let entity = self.tile_index.get(coord)?;
+        let elevation = self
+            .elevation_query
+            .get(entity)
+            .copied()
+            .unwrap_or_default();
+        Some(elevation.height())
+    }
+
+    /// The height of the top face of the highest tile.
+    pub fn max(&self) -> f32 {
+        self.elevation_query
+            .iter()
+            .max()
+            .copied()
+            .unwrap_or_default()
+            .height()
+    }
+
+    /// Find the first tile whose top face or side wall is hit by the ray,
+    /// returning its coordinate and the world position of the hit.
+    ///
+    /// The ray is marched through the hex grid in steps smaller than a tile,
+    /// from the height of the highest tile down to the ground.
+    pub fn raycast(&self, ray: Ray, layout: &HexLayout) -> Option<(HexCoord, Vec3)> {
+        let top_distance = ray.intersect_plane(Vec3::Y * self.max(), Vec3::Y)?;
+        let ground_distance = ray.intersect_plane(Vec3::ZERO, Vec3::Y)?;
+        let top_point = ray.get_point(top_distance);
+        let ground_point = ray.get_point(ground_distance);
+
+        let horizontal_length = (ground_point - top_point).xz().length();
+        let step_length = layout.inradius() / 4.;
+        let steps = (horizontal_length / step_length).ceil().max(1.) as u32;
+
+        for step in 0..=steps {
+            let point = top_point.lerp(ground_point, step as f32 / steps as f32);
+            let coord = layout.world_to_hex(Vec2::new(point.x, -point.z));
+            let Some(height) = self.get(coord) else {
+                continue;
+            };
+            if point.y > height {
+                continue;
+            }
+
+            // The ray is inside this tile's column. If it entered through the
+            // top face, return the exact point on the top face, otherwise it
+            // must have hit a side wall
+            let hit = ray
+                .intersect_plane(Vec3::Y * height, Vec3::Y)
+                .map(|distance| ray.get_point(distance))
+                .filter(|top_hit| layout.world_to_hex(Vec2::new(top_hit.x, -top_hit.z)) == coord)
+                .unwrap_or(point);
+            return Some((coord, hit));
+        }
+
+        None
+    }
+}
@@ -0,0 +1,446 @@
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+use crate::prelude::*;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// An exact position in the hexagonal grid, using integer axial coordinates.
+///
+/// Unlike `TilePosition`, which can point anywhere in the grid, a `HexCoord`
+/// always refers to the center of a single tile. This makes it hashable and
+/// totally ordered, so it can be used as a key for looking up tiles.
+///
+/// Directions such as "north" and "clockwise" are meant as in a grid drawn with
+/// r increasing downwards, like on a page. `HexLayout` lays the grid out with r
+/// increasing along the world's -Z axis, which mirrors it when seen from above,
+/// so "clockwise" here turns counter-clockwise in the world.
+#[derive(
+    Component,
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+)]
+pub struct HexCoord {
+    pub q: i32,
+    pub r: i32,
+}
+
+impl HexCoord {
+    /// The coordinate of the center tile.
+    pub const ZERO: Self = Self::new(0, 0);
+
+    /// A hex vector pointing one tile "north".
+    pub const N: Self = Self::new(0, -1);
+
+    /// A hex vector pointing one tile "north-east".
+    pub const NE: Self = Self::new(1, -1);
+
+    /// A hex vector pointing one tile "south-east".
+    pub const SE: Self = Self::new(1, 0);
+
+    /// A hex vector pointing one tile "south".
+    pub const S: Self = Self::new(0, 1);
+
+    /// A hex vector pointing one tile "south-west".
+    pub const SW: Self = Self::new(-1, 1);
+
+    /// A hex vector pointing one tile "north-west".
+    pub const NW: Self = Self::new(-1, 0);
+
+    /// The six neighbor directions, clockwise starting from "north".
+    pub const DIRECTIONS: [Self; 6] = [Self::N, Self::NE, Self::SE, Self::S, Self::SW, Self::NW];
+
+    /// The six diagonal directions, clockwise starting from between "north"
+    /// and "north-east". Diagonal tiles share a corner but not an edge.
+    pub const DIAGONALS: [Self; 6] = [
+        Self::new(1, -2),
+        Self::new(2, -1),
+        Self::new(1, 1),
+        Self::new(-1, 2),
+        Self::new(-2, 1),
+        Self::new(-1, -1),
+    ];
+
+    /// How far the end points of a line are nudged, so that points landing
+    /// exactly on an edge between two tiles are consistently rounded to the
+    /// same side.
+    pub const LINE_NUDGE: TilePosition = TilePosition::new(1e-6, 2e-6);
+
+    pub const fn new(q: i32, r: i32) -> Self {
+        Self { q, r }
+    }
+
+    pub const fn s(self) -> i32 {
+        -self.q - self.r
+    }
+
+    /// The hex vector for a direction index. Indices wrap around, so 6 is
+    /// "north" again and -1 is "north-west".
+    pub fn from_direction(index: i32) -> Self {
+        Self::DIRECTIONS[index.rem_euclid(6) as usize]
+    }
+
+    /// The index of the direction this hex vector points closest to, or `None`
+    /// for the zero vector.
+    pub fn direction(self) -> Option<i32> {
+        TilePosition::from(self).direction()
+    }
+
+    /// Rotate this hex vector 60° clockwise around the center tile, which is
+    /// counter-clockwise in the world when seen from above.
+    pub fn rotate_cw(self) -> Self {
+        Self::new(-self.r, -self.s())
+    }
+
+    /// Rotate this hex vector 60° counter-clockwise around the center tile,
+    /// which is clockwise in the world when seen from above.
+    pub fn rotate_ccw(self) -> Self {
+        Self::new(-self.s(), -self.q)
+    }
+
+    /// Rotate this coordinate around `center` in 60° steps. Positive steps
+    /// rotate clockwise and negative steps counter-clockwise, so in the world
+    /// a step turns the same way as `Quat::from_rotation_y` by 60°.
+    pub fn rotate_around(self, center: Self, steps: i32) -> Self {
+        let offset = (0..steps.rem_euclid(6)).fold(self - center, |offset, _| offset.rotate_cw());
+        center + offset
+    }
+
+    /// Reflect this hex vector across the Q axis.
+    pub fn reflect_q(self) -> Self {
+        Self::new(self.q, self.s())
+    }
+
+    /// Reflect this hex vector across the R axis.
+    pub fn reflect_r(self) -> Self {
+        Self::new(self.s(), self.r)
+    }
+
+    /// Reflect this hex vector across the S axis.
+    pub fn reflect_s(self) -> Self {
+        Self::new(self.r, self.q)
+    }
+
+    /// The number of tile steps from the center tile to this one.
+    pub fn length(self) -> i32 {
+        (self.q.abs() + self.r.abs() + self.s().abs()) / 2
+    }
+
+    /// The number of tile steps between this tile and another.
+    pub fn distance(self, other: Self) -> i32 {
+        (self - other).length()
+    }
+
+    /// The six tiles sharing an edge with this one, clockwise starting from
+    /// "north".
+    pub fn neighbors(self) -> impl Iterator<Item = Self> {
+        Self::DIRECTIONS.into_iter().map(move |dir| self + dir)
+    }
+
+    /// The six tiles sharing only a corner with this one, clockwise starting
+    /// from between "north" and "north-east".
+    pub fn diagonals(self) -> impl Iterator<Item = Self> {
+        Self::DIAGONALS.into_iter().map(move |dir| self + dir)
+    }
+
+    /// All tiles exactly `radius` steps away from this one, walking clockwise
+    /// around the ring. A radius of 0 yields only this tile.
+    pub fn ring(self, radius: u32) -> impl Iterator<Item = Self> {
+        let radius = radius as i32;
+        let steps = (6 * radius).max(1);
+        (0..steps).map(move |step| {
+            if radius == 0 {
+                return self;
+            }
+            let side = (step / radius) as usize;
+            let corner = self + Self::DIRECTIONS[(side + 4) % 6] * radius;
+            corner + Self::DIRECTIONS[side] * (step % radius)
+        })
+    }
+
+    /// All tiles at most `radius` steps away from this one, ordered by distance
+    /// from the center outwards, ring by ring.
+    pub fn spiral(self, radius: u32) -> impl Iterator<Item = Self> {
+        (0..=radius).flat_map(move |ring_radius| self.ring(ring_radius))
+    }
+
+    /// All tiles at most `radius` steps away from this one, ordered by `q` and
+    /// then `r`.
+    pub fn range(self, radius: u32) -> impl Iterator<Item = Self> {
+        Self::intersecting_range(self, radius, self, radius)
+    }
+
+    /// All tiles that are both within `radius_a` steps of `a` and within
+    /// `radius_b` steps of `b`, ordered by `q` and then `r`.
+    pub fn intersecting_range(
+        a: Self,
+        radius_a: u32,
+        b: Self,
+        radius_b: u32,
+    ) -> impl Iterator<Item = Self> {
+        let (radius_a, radius_b) = (radius_a as i32, radius_b as i32);
+        let q_min = (a.q - radius_a).max(b.q - radius_b);
+        let q_max = (a.q + radius_a).min(b.q + radius_b);
+        let r_min = (a.r - radius_a).max(b.r - radius_b);
+        let r_max = (a.r + radius_a).min(b.r + radius_b);
+        let s_min = (a.s() - radius_a).max(b.s() - radius_b);
+        let s_max = (a.s() + radius_a).min(b.s() + radius_b);
+
+        (q_min..=q_max).flat_map(move |q| {
+            (r_min.max(-q - s_max)..=r_max.min(-q - s_min)).map(move |r| Self::new(q, r))
+        })
+    }
+
+    /// The tiles along a straight line from this tile to another, including
+    /// both ends. Consecutive tiles in the line are always neighbors.
+    pub fn line_to(self, other: Self) -> impl Iterator<Item = Self> {
+        self.line_to_with_nudge(other, Self::LINE_NUDGE)
+    }
+
+    /// Like `line_to`, but with a custom nudge applied to both end points.
+    /// Nudging in opposite directions gives the two possible lines for a line
+    /// running exactly along the edges between tiles.
+    pub fn line_to_with_nudge(
+        self,
+        other: Self,
+        nudge: TilePosition,
+    ) -> impl Iterator<Item = Self> {
+        let start = TilePosition::from(self) + nudge.clone();
+        let end = TilePosition::from(other) + nudge;
+        let steps = self.distance(other);
+
+        (0..=steps).map(move |step| {
+            let t = if steps == 0 {
+                0.
+            } else {
+                step as f32 / steps as f32
+            };
+            Self::from(start.lerp(&end, t))
+        })
+    }
+}
+
+impl Add for HexCoord {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.q + rhs.q, self.r + rhs.r)
+    }
+}
+
+impl AddAssign for HexCoord {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for HexCoord {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.q - rhs.q, self.r - rhs.r)
+    }
+}
+
+impl SubAssign for HexCoord {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Neg for HexCoord {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.q, -self.r)
+    }
+}
+
+impl Mul<i32> for HexCoord {
+    type Output = Self;
+
+    fn mul(self, rhs: i32) -> Self {
+        Self::new(self.q * rhs, self.r * rhs)
+    }
+}
+
+impl<'a> From<&'a TilePosition> for HexCoord {
+    /// Find the coordinate of the tile containing the given tile position
+    fn from(pos: &'a TilePosition) -> Self {
+        let TilePosition { q, r } = pos.round();
+        Self::new(q as i32, r as i32)
+    }
+}
+
+impl From<TilePosition> for HexCoord {
+    fn from(pos: TilePosition) -> Self {
+        HexCoord::from(&pos)
+    }
+}
+
+impl<'a> From<&'a HexCoord> for TilePosition {
+    fn from(HexCoord { q, r }: &'a HexCoord) -> Self {
+        TilePosition::new(*q as f32, *r as f32)
+    }
+}
+
+impl From<HexCoord> for TilePosition {
+    fn from(coord: HexCoord) -> Self {
+        TilePosition::from(&coord)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    /// Coordinates on all sides of the origin, including negative ones.
+    fn sample_coords() -> impl Iterator<Item = HexCoord> {
+        HexCoord::new(-2, 1).range(5)
+    }
+
+    #[test]
+    fn rings_and_ranges_have_the_right_sizes() {
+        let center = HexCoord::new(3, -5);
+        for radius in 0..8 {
+            let r = radius as usize;
+            assert_eq!(center.ring(radius).count(), (6 * r).max(1));
+            assert_eq!(center.range(radius).count(), 3 * r * r + 3 * r + 1);
+            assert_eq!(center.spiral(radius).count(), 3 * r * r + 3 * r + 1);
+        }
+    }
+
+    #[test]
+    fn rings_walk_around_their_center_one_step_at_a_time() {
+        let center = HexCoord::new(-1, 2);
+        for radius in 1..6 {
+            let ring: Vec<_> = center.ring(radius).collect();
+            assert!(ring
+                .iter()
+                .all(|coord| coord.distance(center) == radius as i32));
+            assert_eq!(ring.iter().collect::<HashSet<_>>().len(), ring.len());
+            for (coord, next) in ring.iter().zip(ring.iter().cycle().skip(1)) {
+                assert_eq!(coord.distance(*next), 1);
+            }
+        }
+    }
+
+    #[test]
+    fn spirals_cover_ranges_from_the_center_outwards() {
+        let center = HexCoord::new(2, 2);
+        let spiral: Vec<_> = center.spiral(4).collect();
+        assert_eq!(spiral[0], center);
+        assert!(spiral
+            .windows(2)
+            .all(|pair| pair[0].distance(center) <= pair[1].distance(center)));
+        assert_eq!(
+            spiral.into_iter().collect::<HashSet<_>>(),
+            center.range(4).collect::<HashSet<_>>()
+        );
+    }
+
+    #[test]
+    fn neighbors_and_diagonals_are_one_and_two_steps_away() {
+        for coord in sample_coords() {
+            assert!(coord
+                .neighbors()
+                .all(|neighbor| neighbor.distance(coord) == 1));
+            assert!(coord
+                .diagonals()
+                .all(|diagonal| diagonal.distance(coord) == 2));
+        }
+    }
+
+    #[test]
+    fn intersecting_ranges_match_brute_force() {
+        let cases = [
+            (HexCoord::ZERO, 3, HexCoord::new(2, -1), 2),
+            (HexCoord::new(-4, 1), 4, HexCoord::new(-1, -2), 3),
+            (HexCoord::new(1, 1), 2, HexCoord::new(1, 1), 5),
+            (HexCoord::ZERO, 1, HexCoord::new(5, 0), 2),
+        ];
+        for (a, radius_a, b, radius_b) in cases {
+            let intersection: Vec<_> =
+                HexCoord::intersecting_range(a, radius_a, b, radius_b).collect();
+            let brute_force: HashSet<_> = a
+                .range(radius_a)
+                .filter(|coord| coord.distance(b) <= radius_b as i32)
+                .collect();
+            assert_eq!(intersection.len(), brute_force.len());
+            assert_eq!(
+                intersection.into_iter().collect::<HashSet<_>>(),
+                brute_force
+            );
+        }
+    }
+
+    #[test]
+    fn lines_connect_their_end_points_through_neighbors() {
+        let TilePosition { q, r } = HexCoord::LINE_NUDGE;
+        let nudges = [TilePosition::new(q, r), TilePosition::new(-q, -r)];
+        for from in sample_coords().step_by(7) {
+            for to in sample_coords() {
+                for nudge in nudges.iter() {
+                    let line: Vec<_> = from.line_to_with_nudge(to, nudge.clone()).collect();
+                    assert_eq!(line.len() as i32, from.distance(to) + 1);
+                    assert_eq!(line.first(), Some(&from));
+                    assert_eq!(line.last(), Some(&to));
+                    assert!(line.windows(2).all(|pair| pair[0].distance(pair[1]) == 1));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rotating_six_times_gives_the_same_coordinate() {
+        let center = HexCoord::new(1, -3);
+        for coord in sample_coords() {
+            let rotated = (0..6).fold(coord, |coord, _| coord.rotate_cw());
+            assert_eq!(rotated, coord);
+            assert_eq!(coord.rotate_cw().rotate_ccw(), coord);
+            assert_eq!(coord.rotate_around(center, 6), coord);
+            assert_eq!(
+                coord.rotate_around(center, -1),
+                center + (coord - center).rotate_ccw()
+            );
+            assert_eq!(
+                coord.rotate_around(center, 2).distance(center),
+                coord.distance(center)
+            );
+        }
+    }
+
+    #[test]
+    fn reflecting_twice_gives_the_same_coordinate() {
+        for coord in sample_coords() {
+            assert_eq!(coord.reflect_q().reflect_q(), coord);
+            assert_eq!(coord.reflect_r().reflect_r(), coord);
+            assert_eq!(coord.reflect_s().reflect_s(), coord);
+            assert_eq!(coord.reflect_q().length(), coord.length());
+        }
+    }
+
+    #[test]
+    fn directions_turn_into_indices_and_back() {
+        for index in 0..6 {
+            assert_eq!(HexCoord::from_direction(index).direction(), Some(index));
+            assert_eq!(
+                (HexCoord::from_direction(index) * 3).direction(),
+                Some(index)
+            );
+        }
+        assert_eq!(HexCoord::from_direction(6), HexCoord::N);
+        assert_eq!(HexCoord::from_direction(-1), HexCoord::NW);
+        assert_eq!(HexCoord::ZERO.direction(), None);
+    }
+}
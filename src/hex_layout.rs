@@ -0,0 +1,136 @@
+use std::f32::consts::PI;
+
+use crate::{offset_coord::OffsetKind, prelude::*};
+use bevy::prelude::*;
+
+const SQRT_3: f32 = 1.7320508;
+
+/// Which way the hexagons in the grid are turned.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HexOrientation {
+    /// Hexagons have a flat edge facing "north", and columns of tiles line up
+    /// vertically.
+    #[default]
+    FlatTop,
+    /// Hexagons have a corner facing "north", and rows of tiles line up
+    /// horizontally.
+    PointyTop,
+}
+
+impl HexOrientation {
+    /// The Q and R basis vectors for a tile size of 1.
+    fn basis(self) -> (Vec2, Vec2) {
+        match self {
+            HexOrientation::FlatTop => (Vec2::new(3. / 2., SQRT_3 / 2.), Vec2::new(0., SQRT_3)),
+            HexOrientation::PointyTop => (Vec2::new(SQRT_3, 0.), Vec2::new(SQRT_3 / 2., 3. / 2.)),
+        }
+    }
+
+    /// The offset coordinate system whose rows or columns line up with the
+    /// tiles in this orientation.
+    pub fn offset_kind(self) -> OffsetKind {
+        match self {
+            HexOrientation::FlatTop => OffsetKind::OddQ,
+            HexOrientation::PointyTop => OffsetKind::OddR,
+        }
+    }
+
+    /// The angle of the first corner of a hexagon, counter-clockwise from the
+    /// world X axis.
+    fn start_angle(self) -> f32 {
+        match self {
+            HexOrientation::FlatTop => 0.,
+            HexOrientation::PointyTop => PI / 6.,
+        }
+    }
+}
+
+/// Describes how the hexagonal grid is laid out in the world, and converts
+/// between tile positions and world positions accordingly.
+///
+/// World space `Vec2`s lie in the ground plane, with `x` along the world X axis
+/// and `y` along the world negative Z axis.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct HexLayout {
+    pub orientation: HexOrientation,
+    /// The circumradius of each tile, i.e. the distance from its center to
+    /// any of its corners.
+    pub size: f32,
+    /// The world position of the center of the tile at `HexCoord::ZERO`.
+    pub origin: Vec2,
+}
+
+impl Default for HexLayout {
+    fn default() -> Self {
+        Self {
+            orientation: HexOrientation::FlatTop,
+            size: 0.5,
+            origin: Vec2::ZERO,
+        }
+    }
+}
+
+impl HexLayout {
+    /// The distance from the center of a tile to the middle of any of its
+    /// edges.
+    pub fn inradius(&self) -> f32 {
+        SQRT_3 * self.size / 2.
+    }
+
+    /// Translate a tile position in the hexagonal grid into a world position
+    pub fn tile_to_world(&self, TilePosition { q, r }: &TilePosition) -> Vec2 {
+        let (q_vec2, r_vec2) = self.orientation.basis();
+        self.origin + (q_vec2 * *q + r_vec2 * *r) * self.size
+    }
+
+    /// Translate a world position into a tile position in the hexagonal grid
+    pub fn world_to_tile(&self, world: Vec2) -> TilePosition {
+        let (q_vec2, r_vec2) = self.orientation.basis();
+        let inverse = Mat2::from_cols(q_vec2, r_vec2).inverse();
+        let Vec2 { x: q, y: r } = inverse * ((world - self.origin) / self.size);
+        TilePosition::new(q, r)
+    }
+
+    /// Translate a tile coordinate into the world position of its center
+    pub fn hex_to_world(&self, coord: HexCoord) -> Vec2 {
+        self.tile_to_world(&coord.into())
+    }
+
+    /// Find the coordinate of the tile containing the given world position
+    pub fn world_to_hex(&self, world: Vec2) -> HexCoord {
+        self.world_to_tile(world).into()
+    }
+
+    /// Translate a tile position into a 3D world position at the given height.
+    pub fn tile_to_vec3_with_y(&self, pos: &TilePosition, y: f32) -> Vec3 {
+        let Vec2 { x, y: z } = self.tile_to_world(pos);
+        Vec3::new(x, y, -z)
+    }
+
+    /// Translate a tile coordinate into a 3D world position at the given
+    /// height.
+    pub fn hex_to_vec3_with_y(&self, coord: HexCoord, y: f32) -> Vec3 {
+        self.tile_to_vec3_with_y(&coord.into(), y)
+    }
+
+    /// Translate a 3D world position into a tile position, ignoring its height.
+    pub fn vec3_to_tile(&self, Vec3 { x, z, .. }: Vec3) -> TilePosition {
+        self.world_to_tile(Vec2::new(x, -z))
+    }
+
+    /// The world space offset between neighboring tiles in the given direction.
+    pub fn direction_vec2(&self, index: i32) -> Vec2 {
+        self.tile_to_world(&TilePosition::from_direction(index)) - self.origin
+    }
+
+    /// The world positions of the six corners of the tile containing `pos`.
+    pub fn corners(&self, pos: &TilePosition) -> impl Iterator<Item = Vec2> {
+        let center = self.tile_to_world(&pos.round());
+        let start_angle = self.orientation.start_angle();
+        let size = self.size;
+        (0..6).map(move |i| {
+            let angle = start_angle + PI / 3. * i as f32;
+            center + Vec2::new(angle.cos(), angle.sin()) * size
+        })
+    }
+}
@@ -0,0 +1,339 @@
+use std::collections::HashMap;
+
+use crate::{
+    offset_coord::{OffsetCoord, OffsetKind},
+    prelude::*,
+};
+
+/// A map from tile coordinates to values with constant-time lookup.
+///
+/// Maps created for a hexagon or rectangle shape store their values densely in
+/// a `Vec`, while sparse maps use a `HashMap` and can hold any set of
+/// coordinates. Inserting a value outside the shape of a dense map converts it
+/// into a sparse map, so inserting never fails.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HexMap<T> {
+    storage: Storage<T>,
+    len: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Storage<T> {
+    Hexagon {
+        center: HexCoord,
+        radius: i32,
+        cells: Vec<Option<T>>,
+    },
+    Rectangle {
+        kind: OffsetKind,
+        min: OffsetCoord,
+        width: i32,
+        height: i32,
+        cells: Vec<Option<T>>,
+    },
+    Sparse(HashMap<HexCoord, T>),
+}
+
+impl<T> Default for HexMap<T> {
+    fn default() -> Self {
+        Self::sparse()
+    }
+}
+
+impl<T> HexMap<T> {
+    /// An empty map that can hold any coordinates.
+    pub fn sparse() -> Self {
+        Self {
+            storage: Storage::Sparse(HashMap::new()),
+            len: 0,
+        }
+    }
+
+    /// An empty map with dense storage for every tile within `radius` steps
+    /// of `center`.
+    pub fn hexagon(center: HexCoord, radius: u32) -> Self {
+        let side = 2 * radius as usize + 1;
+        Self {
+            storage: Storage::Hexagon {
+                center,
+                radius: radius as i32,
+                cells: empty_cells(side * side),
+            },
+            len: 0,
+        }
+    }
+
+    /// An empty map with dense storage for a rectangle of tiles `width`
+    /// columns wide and `height` rows tall in offset coordinates, starting
+    /// at `min`.
+    pub fn rectangle(kind: OffsetKind, min: OffsetCoord, width: u32, height: u32) -> Self {
+        Self {
+            storage: Storage::Rectangle {
+                kind,
+                min,
+                width: width as i32,
+                height: height as i32,
+                cells: empty_cells(width as usize * height as usize),
+            },
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn contains(&self, coord: HexCoord) -> bool {
+        self.get(coord).is_some()
+    }
+
+    pub fn get(&self, coord: HexCoord) -> Option<&T> {
+        match &self.storage {
+            Storage::Sparse(values) => values.get(&coord),
+            Storage::Hexagon { cells, .. } | Storage::Rectangle { cells, .. } => {
+                cells[self.storage.index(coord)?].as_ref()
+            }
+        }
+    }
+
+    pub fn get_mut(&mut self, coord: HexCoord) -> Option<&mut T> {
+        let index = self.storage.index(coord);
+        match &mut self.storage {
+            Storage::Sparse(values) => values.get_mut(&coord),
+            Storage::Hexagon { cells, .. } | Storage::Rectangle { cells, .. } => {
+                cells[index?].as_mut()
+            }
+        }
+    }
+
+    /// Insert a value at the given coordinate, returning the value that was
+    /// there before, if any.
+    pub fn insert(&mut self, coord: HexCoord, value: T) -> Option<T> {
+        if self.storage.index(coord).is_none() {
+            self.make_sparse();
+        }
+
+        let index = self.storage.index(coord);
+        let previous = match &mut self.storage {
+            Storage::Sparse(values) => values.insert(coord, value),
+            Storage::Hexagon { cells, .. } | Storage::Rectangle { cells, .. } => {
+                cells[index?].replace(value)
+            }
+        };
+        if previous.is_none() {
+            self.len += 1;
+        }
+        previous
+    }
+
+    /// Remove the value at the given coordinate, returning it if there was
+    /// one.
+    pub fn remove(&mut self, coord: HexCoord) -> Option<T> {
+        let index = self.storage.index(coord);
+        let removed = match &mut self.storage {
+            Storage::Sparse(values) => values.remove(&coord),
+            Storage::Hexagon { cells, .. } | Storage::Rectangle { cells, .. } => {
+                cells[index?].take()
+            }
+        };
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    /// All coordinates and their values, in no particular order.
+    pub fn iter(&self) -> Box<dyn Iterator<Item = (HexCoord, &T)> + '_> {
+        match &self.storage {
+            Storage::Sparse(values) => {
+                Box::new(values.iter().map(|(coord, value)| (*coord, value)))
+            }
+            Storage::Hexagon { cells, .. } | Storage::Rectangle { cells, .. } => Box::new(
+                cells
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, cell)| Some((self.storage.coord(index), cell.as_ref()?))),
+            ),
+        }
+    }
+
+    fn make_sparse(&mut self) {
+        let cell_count = match &self.storage {
+            Storage::Sparse(_) => return,
+            Storage::Hexagon { cells, .. } | Storage::Rectangle { cells, .. } => cells.len(),
+        };
+        let coords: Vec<_> = (0..cell_count)
+            .map(|index| self.storage.coord(index))
+            .collect();
+        let storage = std::mem::replace(&mut self.storage, Storage::Sparse(HashMap::new()));
+        if let Storage::Hexagon { cells, .. } | Storage::Rectangle { cells, .. } = storage {
+            let values = coords
+                .into_iter()
+                .zip(cells)
+                .filter_map(|(coord, cell)| Some((coord, cell?)))
+                .collect();
+            self.storage = Storage::Sparse(values);
+        }
+    }
+}
+
+impl<T> Storage<T> {
+    /// The index into the dense cells for a coordinate, or `None` if the
+    /// coordinate is outside the shape or the storage is sparse.
+    fn index(&self, coord: HexCoord) -> Option<usize> {
+        match self {
+            Storage::Sparse(_) => None,
+            Storage::Hexagon { center, radius, .. } => {
+                let offset = coord - *center;
+                if offset.length() > *radius {
+                    return None;
+                }
+                let side = 2 * radius + 1;
+                Some(((offset.q + radius) * side + offset.r + radius) as usize)
+            }
+            Storage::Rectangle {
+                kind,
+                min,
+                width,
+                height,
+                ..
+            } => {
+                let offset = OffsetCoord::from_hex(coord, *kind);
+                let (col, row) = (offset.col - min.col, offset.row - min.row);
+                if !(0..*width).contains(&col) || !(0..*height).contains(&row) {
+                    return None;
+                }
+                Some((row * width + col) as usize)
+            }
+        }
+    }
+
+    /// The coordinate for an index into the dense cells. The inverse of
+    /// `index`.
+    fn coord(&self, index: usize) -> HexCoord {
+        let index = index as i32;
+        match self {
+            Storage::Sparse(_) => unreachable!("sparse storage has no cell indices"),
+            Storage::Hexagon { center, radius, .. } => {
+                let side = 2 * radius + 1;
+                *center + HexCoord::new(index / side - radius, index % side - radius)
+            }
+            Storage::Rectangle {
+                kind, min, width, ..
+            } => OffsetCoord::new(min.col + index % width, min.row + index / width).to_hex(*kind),
+        }
+    }
+}
+
+fn empty_cells<T>(len: usize) -> Vec<Option<T>> {
+    std::iter::repeat_with(|| None).take(len).collect()
+}
+
+impl<T> FromIterator<(HexCoord, T)> for HexMap<T> {
+    fn from_iter<I: IntoIterator<Item = (HexCoord, T)>>(iter: I) -> Self {
+        let mut map = Self::sparse();
+        for (coord, value) in iter {
+            map.insert(coord, value);
+        }
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KINDS: [OffsetKind; 4] = [
+        OffsetKind::OddR,
+        OffsetKind::EvenR,
+        OffsetKind::OddQ,
+        OffsetKind::EvenQ,
+    ];
+
+    fn is_dense<T>(map: &HexMap<T>) -> bool {
+        !matches!(map.storage, Storage::Sparse(_))
+    }
+
+    /// Inserts every coordinate as its own value, and checks that they can all
+    /// be found again without the map turning sparse.
+    fn fill_and_check(map: &mut HexMap<HexCoord>, coords: &[HexCoord]) {
+        for coord in coords {
+            assert_eq!(map.insert(*coord, *coord), None);
+        }
+        assert!(is_dense(map));
+        assert_eq!(map.len(), coords.len());
+        for coord in coords {
+            assert_eq!(map.get(*coord), Some(coord));
+        }
+        assert!(map.iter().all(|(coord, value)| coord == *value));
+        assert_eq!(map.iter().count(), coords.len());
+    }
+
+    #[test]
+    fn hexagons_hold_every_tile_in_their_shape() {
+        let center = HexCoord::new(-2, 3);
+        let mut map = HexMap::hexagon(center, 3);
+        let coords: Vec<_> = center.range(3).collect();
+        fill_and_check(&mut map, &coords);
+
+        let outside = center + HexCoord::N * 4;
+        assert_eq!(map.get(outside), None);
+        assert_eq!(map.remove(outside), None);
+        assert!(is_dense(&map));
+    }
+
+    #[test]
+    fn rectangles_hold_every_tile_in_their_shape() {
+        let min = OffsetCoord::new(-3, -2);
+        for kind in KINDS {
+            let mut map = HexMap::rectangle(kind, min, 5, 4);
+            let coords: Vec<_> = (-3..2)
+                .flat_map(|col| (-2..2).map(move |row| OffsetCoord::new(col, row).to_hex(kind)))
+                .collect();
+            fill_and_check(&mut map, &coords);
+
+            let outside = OffsetCoord::new(-4, 0).to_hex(kind);
+            assert_eq!(map.get(outside), None);
+            assert!(is_dense(&map));
+        }
+    }
+
+    #[test]
+    fn removing_at_the_edges_frees_the_tile() {
+        let mut map = HexMap::hexagon(HexCoord::ZERO, 2);
+        let edges: Vec<_> = HexCoord::ZERO.ring(2).collect();
+        for coord in edges.iter() {
+            map.insert(*coord, coord.q);
+        }
+
+        for (removed, coord) in edges.iter().enumerate() {
+            assert_eq!(map.remove(*coord), Some(coord.q));
+            assert_eq!(map.get(*coord), None);
+            assert_eq!(map.remove(*coord), None);
+            assert_eq!(map.len(), edges.len() - removed - 1);
+        }
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn inserting_outside_the_shape_keeps_existing_values() {
+        let mut map = HexMap::hexagon(HexCoord::new(1, -1), 2);
+        let coords: Vec<_> = HexCoord::new(1, -1).range(2).collect();
+        for coord in coords.iter() {
+            map.insert(*coord, *coord);
+        }
+
+        let outside = HexCoord::new(-5, 4);
+        assert_eq!(map.insert(outside, outside), None);
+        assert!(!is_dense(&map));
+        assert_eq!(map.len(), coords.len() + 1);
+        assert_eq!(map.get(outside), Some(&outside));
+        for coord in coords.iter() {
+            assert_eq!(map.get(*coord), Some(coord));
+        }
+    }
+}
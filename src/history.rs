@@ -0,0 +1,254 @@
+use std::collections::VecDeque;
+
+use crate::{prelude::*, state::GameState};
+use bevy::prelude::*;
+
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CommandHistory>()
+            .add_systems(
+                Update,
+                undo_redo
+                    .run_if(in_state(GameState::Playing).or_else(in_state(GameState::Demolishing))),
+            )
+            .add_systems(OnExit(AppState::InGame), clear_history);
+    }
+}
+
+/// How many commands can be undone before the oldest ones are forgotten.
+const HISTORY_CAPACITY: usize = 100;
+
+/// An edit to the game that can be reverted and applied again, such as
+/// placing a structure.
+pub trait UndoableCommand: Send + Sync {
+    /// Perform the edit again after it has been reverted.
+    fn apply(&mut self, world: &mut World);
+    /// Undo the edit.
+    fn revert(&mut self, world: &mut World);
+    /// Whether the edit can be applied again right now, such as whether the
+    /// player can still afford it.
+    fn can_apply(&self, _world: &World) -> bool {
+        true
+    }
+    /// Whether the edit can be undone right now.
+    fn can_revert(&self, _world: &World) -> bool {
+        true
+    }
+}
+
+/// The edits made in the current game, in the order they were made, so that
+/// they can be undone with Ctrl+Z and redone with Ctrl+Y.
+#[derive(Resource)]
+pub struct CommandHistory {
+    done: VecDeque<Box<dyn UndoableCommand>>,
+    undone: Vec<Box<dyn UndoableCommand>>,
+    capacity: usize,
+}
+
+impl Default for CommandHistory {
+    fn default() -> Self {
+        Self::with_capacity(HISTORY_CAPACITY)
+    }
+}
+
+impl CommandHistory {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            done: VecDeque::new(),
+            undone: Vec::new(),
+            capacity,
+        }
+    }
+
+    /// Record an edit that has already been made. Any undone edits can no
+    /// longer be redone after this.
+    pub fn push(&mut self, command: impl UndoableCommand + 'static) {
+        self.undone.clear();
+        self.done.push_back(Box::new(command));
+        while self.done.len() > self.capacity {
+            self.done.pop_front();
+        }
+    }
+
+    /// Make an edit and record it.
+    pub fn execute(&mut self, world: &mut World, mut command: impl UndoableCommand + 'static) {
+        command.apply(world);
+        self.push(command);
+    }
+
+    /// Revert the most recent edit. Returns `false` if there was nothing to
+    /// undo, or if it can't be undone right now.
+    pub fn undo(&mut self, world: &mut World) -> bool {
+        let Some(mut command) = self.done.pop_back() else {
+            return false;
+        };
+        if !command.can_revert(world) {
+            self.done.push_back(command);
+            return false;
+        }
+        command.revert(world);
+        self.undone.push(command);
+        true
+    }
+
+    /// Apply the most recently undone edit again. Returns `false` if there was
+    /// nothing to redo, or if it can't be applied right now.
+    pub fn redo(&mut self, world: &mut World) -> bool {
+        let Some(mut command) = self.undone.pop() else {
+            return false;
+        };
+        if !command.can_apply(world) {
+            self.undone.push(command);
+            return false;
+        }
+        command.apply(world);
+        self.done.push_back(command);
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.done.clear();
+        self.undone.clear();
+    }
+}
+
+fn undo_redo(world: &mut World) {
+    let keyboard_input = world.resource::<Input<KeyCode>>();
+    if !keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+    let shift_pressed = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let undo = keyboard_input.just_pressed(KeyCode::Z) && !shift_pressed;
+    let redo = keyboard_input.just_pressed(KeyCode::Y)
+        || (keyboard_input.just_pressed(KeyCode::Z) && shift_pressed);
+
+    if undo || redo {
+        world.resource_scope(|world, mut history: Mut<CommandHistory>| {
+            if undo {
+                history.undo(world);
+            } else {
+                history.redo(world);
+            }
+        });
+    }
+}
+
+fn clear_history(mut history: ResMut<CommandHistory>) {
+    history.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Resource, Default)]
+    struct Total(i32);
+
+    /// Adds to the `Total`, unless it's `pinned`, in which case it can't be
+    /// undone.
+    struct Add {
+        amount: i32,
+        pinned: bool,
+    }
+
+    impl Add {
+        fn new(amount: i32) -> Self {
+            Self {
+                amount,
+                pinned: false,
+            }
+        }
+    }
+
+    impl UndoableCommand for Add {
+        fn apply(&mut self, world: &mut World) {
+            world.resource_mut::<Total>().0 += self.amount;
+        }
+
+        fn revert(&mut self, world: &mut World) {
+            world.resource_mut::<Total>().0 -= self.amount;
+        }
+
+        fn can_revert(&self, _world: &World) -> bool {
+            !self.pinned
+        }
+    }
+
+    fn total(world: &World) -> i32 {
+        world.resource::<Total>().0
+    }
+
+    #[test]
+    fn undo_and_redo_walk_through_the_history() {
+        let mut world = World::new();
+        world.init_resource::<Total>();
+        let mut history = CommandHistory::default();
+        history.execute(&mut world, Add::new(1));
+        history.execute(&mut world, Add::new(10));
+
+        assert!(history.undo(&mut world));
+        assert_eq!(total(&world), 1);
+        assert!(history.undo(&mut world));
+        assert!(!history.undo(&mut world));
+        assert_eq!(total(&world), 0);
+
+        assert!(history.redo(&mut world));
+        assert!(history.redo(&mut world));
+        assert!(!history.redo(&mut world));
+        assert_eq!(total(&world), 11);
+    }
+
+    #[test]
+    fn executing_forgets_undone_commands() {
+        let mut world = World::new();
+        world.init_resource::<Total>();
+        let mut history = CommandHistory::default();
+        history.execute(&mut world, Add::new(1));
+        history.execute(&mut world, Add::new(10));
+        history.undo(&mut world);
+        history.execute(&mut world, Add::new(100));
+
+        assert!(!history.redo(&mut world));
+        assert_eq!(total(&world), 101);
+        assert!(history.undo(&mut world));
+        assert_eq!(total(&world), 1);
+    }
+
+    #[test]
+    fn the_oldest_commands_are_forgotten_past_the_capacity() {
+        let mut world = World::new();
+        world.init_resource::<Total>();
+        let mut history = CommandHistory::default();
+        for _ in 0..HISTORY_CAPACITY + 1 {
+            history.execute(&mut world, Add::new(1));
+        }
+
+        for _ in 0..HISTORY_CAPACITY {
+            assert!(history.undo(&mut world));
+        }
+        assert!(!history.undo(&mut world));
+        assert_eq!(total(&world), 1);
+    }
+
+    #[test]
+    fn commands_that_cant_be_reverted_stay_in_the_history() {
+        let mut world = World::new();
+        world.init_resource::<Total>();
+        let mut history = CommandHistory::default();
+        history.execute(&mut world, Add::new(1));
+        history.execute(
+            &mut world,
+            Add {
+                amount: 10,
+                pinned: true,
+            },
+        );
+
+        assert!(!history.undo(&mut world));
+        assert!(!history.undo(&mut world));
+        assert!(!history.redo(&mut world));
+        assert_eq!(total(&world), 11);
+    }
+}
@@ -0,0 +1,15 @@
+//! The hex grid the game is played on: coordinates and their algorithms,
+//! layouts, storage, terrain and pathfinding. None of it depends on the game's
+//! systems, so it can be used and tested on its own.
+
+pub mod hex_coord;
+pub mod hex_layout;
+pub mod hex_map;
+pub mod offset_coord;
+pub mod pathfinding;
+pub mod terrain;
+pub mod tile_position;
+
+pub mod prelude {
+    pub use crate::{hex_coord::HexCoord, hex_layout::HexLayout, tile_position::TilePosition};
+}
@@ -1,17 +1,28 @@
 mod camera;
+mod chunks;
+mod clock;
 mod defaults;
+mod elevation;
 mod ground;
+mod history;
 mod lights;
+mod map_file;
+mod map_generation;
+mod map_shape;
 mod mouse_position;
 mod prelude;
+mod save;
+mod sight;
 mod spring;
 mod state;
+mod stockpile;
 mod structure;
-mod tile_position;
+mod tile_transform;
 mod tiles;
 mod ui;
 
 use bevy::prelude::*;
+use roost::{hex_coord, hex_layout, hex_map, offset_coord, terrain, tile_position};
 use ui::*;
 // use bevy_inspector_egui::quick::WorldInspectorPlugin;
 
@@ -29,8 +40,18 @@ fn main() {
             mouse_position::MousePositionPlugin,
             ground::GroundPlugin,
             structure::StructurePlugin,
+        ))
+        .add_plugins((
+            map_file::MapFilePlugin,
+            map_shape::MapShapePlugin,
             tiles::TilesPlugin,
-            tile_position::TilePositionPlugin,
+            chunks::ChunksPlugin,
+            tile_transform::TileTransformPlugin,
+            sight::SightPlugin,
+            clock::ClockPlugin,
+            save::SavePlugin,
+            history::HistoryPlugin,
+            stockpile::StockpilePlugin,
         ))
         .run();
 }
@@ -0,0 +1,231 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    utils::BoxedFuture,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    elevation::Elevation,
+    map_generation::Deposit,
+    prelude::*,
+    structure::{Facing, StructureId},
+    terrain::Terrain,
+};
+
+pub struct MapFilePlugin;
+
+impl Plugin for MapFilePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<MapFile>()
+            .register_asset_loader(MapFileLoader);
+    }
+}
+
+/// A hand-authored map, listing every tile of the board and the structures
+/// placed on it from the start. Loaded from `.map.ron` or `.map.json` files:
+///
+/// ```ron
+/// (
+///     tiles: [
+///         (coord: (q: 0, r: 0), terrain: Grass, elevation: 1),
+///         (coord: (q: 1, r: 0), terrain: Rock, elevation: 3, deposit: Some(Stone)),
+///     ],
+///     structures: [
+///         (structure: "house", coord: (q: 0, r: 0), facing: 2),
+///     ],
+/// )
+/// ```
+#[derive(Asset, TypePath, Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MapFile {
+    pub tiles: Vec<MapFileTile>,
+    #[serde(default)]
+    pub structures: Vec<MapFileStructure>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MapFileTile {
+    pub coord: HexCoord,
+    pub terrain: Terrain,
+    #[serde(default)]
+    pub elevation: Elevation,
+    #[serde(default)]
+    pub deposit: Option<Deposit>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MapFileStructure {
+    /// The id of the structure's definition in `assets/structures`.
+    pub structure: StructureId,
+    pub coord: HexCoord,
+    /// How many 60° steps the structure is turned from its default direction.
+    #[serde(default)]
+    pub facing: Facing,
+}
+
+impl MapFile {
+    pub fn from_ron(text: &str) -> Result<Self, MapFileErrorKind> {
+        let map_file: Self = ron::from_str(text).map_err(MapFileErrorKind::Ron)?;
+        map_file.validate()?;
+        Ok(map_file)
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, MapFileErrorKind> {
+        let map_file: Self = serde_json::from_str(text).map_err(MapFileErrorKind::Json)?;
+        map_file.validate()?;
+        Ok(map_file)
+    }
+
+    /// Check that no two tiles share a coordinate, and that every structure
+    /// stands on a tile. Structure definitions may not be loaded yet, so the
+    /// rest of their footprints can't be checked here.
+    ///
+    /// Neither RON nor JSON deserialization keeps track of where values came
+    /// from, so errors point at entries by their index in `tiles` or
+    /// `structures` rather than by line.
+    fn validate(&self) -> Result<(), MapFileErrorKind> {
+        let mut tile_indices = HashMap::new();
+        for (index, tile) in self.tiles.iter().enumerate() {
+            if let Some(first_index) = tile_indices.insert(tile.coord, index) {
+                return Err(MapFileErrorKind::DuplicateTile {
+                    coord: tile.coord,
+                    index,
+                    first_index,
+                });
+            }
+        }
+
+        for (index, structure) in self.structures.iter().enumerate() {
+            if !tile_indices.contains_key(&structure.coord) {
+                return Err(MapFileErrorKind::MissingTile {
+                    structure: structure.structure.clone(),
+                    coord: structure.coord,
+                    index,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A map file that couldn't be loaded, and why.
+#[derive(Debug)]
+pub struct MapFileError {
+    pub path: PathBuf,
+    pub kind: MapFileErrorKind,
+}
+
+#[derive(Debug)]
+pub enum MapFileErrorKind {
+    Io(std::io::Error),
+    /// The file isn't valid RON, or doesn't match the map format, such as
+    /// when it names an unknown terrain.
+    Ron(ron::error::SpannedError),
+    /// The file isn't valid JSON, or doesn't match the map format, such as
+    /// when it names an unknown terrain.
+    Json(serde_json::Error),
+    /// The tile at `index` in `tiles` has the same coordinate as the one at
+    /// `first_index`.
+    DuplicateTile {
+        coord: HexCoord,
+        index: usize,
+        first_index: usize,
+    },
+    /// The structure at `index` in `structures` stands where there is no tile.
+    MissingTile {
+        structure: StructureId,
+        coord: HexCoord,
+        index: usize,
+    },
+}
+
+impl fmt::Display for MapFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = self.path.display();
+        match &self.kind {
+            MapFileErrorKind::Io(error) => write!(f, "{path}: could not read map file: {error}"),
+            MapFileErrorKind::Ron(error) => write!(
+                f,
+                "{path}:{}:{}: {}",
+                error.position.line, error.position.col, error.code
+            ),
+            MapFileErrorKind::Json(error) => write!(f, "{path}: {error}"),
+            MapFileErrorKind::DuplicateTile {
+                coord,
+                index,
+                first_index,
+            } => write!(
+                f,
+                "{path}: tiles[{index}]: duplicate tile at (q: {}, r: {}), first defined in tiles[{first_index}]",
+                coord.q, coord.r
+            ),
+            MapFileErrorKind::MissingTile {
+                structure,
+                coord,
+                index,
+            } => write!(
+                f,
+                "{path}: structures[{index}]: {structure:?} stands on (q: {}, r: {}), where there is no tile",
+                coord.q, coord.r
+            ),
+        }
+    }
+}
+
+impl Error for MapFileError {}
+
+/// Loads `MapFile` assets from `.map.ron` and `.map.json` files.
+struct MapFileLoader;
+
+impl AssetLoader for MapFileLoader {
+    type Asset = MapFile;
+    type Settings = ();
+    type Error = MapFileError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<MapFile, MapFileError>> {
+        Box::pin(async move {
+            let path = load_context.path().to_path_buf();
+            let error = |kind| MapFileError {
+                path: path.clone(),
+                kind,
+            };
+
+            let mut text = String::new();
+            reader
+                .read_to_string(&mut text)
+                .await
+                .map_err(|io_error| error(MapFileErrorKind::Io(io_error)))?;
+
+            if is_json(&path) {
+                MapFile::from_json(&text).map_err(error)
+            } else {
+                MapFile::from_ron(&text).map_err(error)
+            }
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["map.ron", "map.json"]
+    }
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == "json")
+}
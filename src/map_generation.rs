@@ -0,0 +1,368 @@
+use crate::{
+    elevation::Elevation, hex_layout::HexOrientation, hex_map::HexMap, map_shape::MapShape,
+    prelude::*, terrain::Terrain,
+};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A natural resource found on a tile, which structures can later exploit.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Deposit {
+    Stone,
+    Berries,
+    Gold,
+}
+
+/// Where deposits of a kind may be placed.
+struct DepositRule {
+    deposit: Deposit,
+    terrains: &'static [Terrain],
+    /// How many tiles of the map there are per deposit of this kind.
+    tiles_per_deposit: usize,
+    /// The minimum number of steps between this and any other deposit.
+    min_spacing: i32,
+}
+
+const DEPOSIT_RULES: [DepositRule; 3] = [
+    DepositRule {
+        deposit: Deposit::Stone,
+        terrains: &[Terrain::Rock],
+        tiles_per_deposit: 15,
+        min_spacing: 2,
+    },
+    DepositRule {
+        deposit: Deposit::Berries,
+        terrains: &[Terrain::Grass, Terrain::Forest],
+        tiles_per_deposit: 15,
+        min_spacing: 2,
+    },
+    DepositRule {
+        deposit: Deposit::Gold,
+        terrains: &[Terrain::Rock, Terrain::Sand],
+        tiles_per_deposit: 30,
+        min_spacing: 3,
+    },
+];
+
+/// Tiles within this many steps of the center are kept free of water and
+/// deposits, so that there is always room to start building.
+const START_AREA_RADIUS: u32 = 1;
+
+/// Noise layers, so that each property of a tile gets independent noise from
+/// the same seed.
+const ELEVATION_LAYER: u64 = 1;
+const MOISTURE_LAYER: u64 = 2;
+
+/// Tiles with an elevation below this are covered by water.
+const SEA_LEVEL: f32 = 0.3;
+
+/// How much the elevation of a tile has to rise above sea level to raise the
+/// tile by one more elevation step.
+const ELEVATION_PER_STEP: f32 = 0.1;
+
+/// The world distance covered by one cell of the coarsest noise octave,
+/// relative to the default tile size.
+const NOISE_SCALE: f32 = 2.;
+
+/// One tile of a generated map.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratedTile {
+    pub terrain: Terrain,
+    /// Height of the tile, between 0 and 1.
+    pub elevation: f32,
+    /// Wetness of the tile, between 0 and 1.
+    pub moisture: f32,
+    /// Whether a river flows through the tile.
+    pub river: bool,
+    pub deposit: Option<Deposit>,
+}
+
+impl GeneratedTile {
+    /// The elevation steps of the tile. Water always lies at ground level,
+    /// and land is raised by at least one step.
+    pub fn elevation_steps(&self) -> Elevation {
+        if self.terrain == Terrain::Water {
+            return Elevation(0);
+        }
+        let steps = ((self.elevation - SEA_LEVEL) / ELEVATION_PER_STEP).max(0.) as u32;
+        Elevation(steps + 1)
+    }
+}
+
+/// Generates maps from a seed, so that the same seed and shape always give the
+/// same map.
+#[derive(Resource, Debug, Clone)]
+pub struct MapGenerator {
+    pub seed: u64,
+    pub shape: MapShape,
+}
+
+impl Default for MapGenerator {
+    fn default() -> Self {
+        Self::new(0x5EED, MapShape::default())
+    }
+}
+
+impl MapGenerator {
+    pub fn new(seed: u64, shape: MapShape) -> Self {
+        Self { seed, shape }
+    }
+
+    pub fn generate(&self, orientation: HexOrientation) -> HexMap<GeneratedTile> {
+        let coords = self.shape.coords(orientation);
+        let mut tiles = self.shape.new_map(orientation);
+        for coord in coords.iter().copied() {
+            tiles.insert(coord, self.generate_tile(coord));
+        }
+
+        let mut rng = SplitMix64::new(self.seed);
+        self.add_rivers(&mut tiles, &coords, &mut rng);
+        self.add_deposits(&mut tiles, &coords, &mut rng);
+        tiles
+    }
+
+    fn generate_tile(&self, coord: HexCoord) -> GeneratedTile {
+        // Sample noise in world space rather than tile space so that features
+        // aren't stretched along the hex axes
+        let pos = HexLayout::default().hex_to_world(coord) / NOISE_SCALE;
+        let elevation = fractal_noise(self.seed, ELEVATION_LAYER, pos);
+        let moisture = fractal_noise(self.seed, MOISTURE_LAYER, pos);
+
+        let terrain = if coord.length() <= START_AREA_RADIUS as i32 {
+            Terrain::Grass
+        } else {
+            biome(elevation, moisture)
+        };
+
+        GeneratedTile {
+            terrain,
+            elevation,
+            moisture,
+            river: false,
+            deposit: None,
+        }
+    }
+
+    /// Carve rivers starting from high tiles and flowing to the lowest
+    /// neighbor until they reach water, the edge of the map or a dip in the
+    /// ground.
+    fn add_rivers(
+        &self,
+        tiles: &mut HexMap<GeneratedTile>,
+        coords: &[HexCoord],
+        rng: &mut SplitMix64,
+    ) {
+        let mut sources: Vec<_> = coords
+            .iter()
+            .copied()
+            .filter(|coord| {
+                coord.length() > START_AREA_RADIUS as i32
+                    && tiles
+                        .get(*coord)
+                        .is_some_and(|tile| tile.elevation > 0.6 && tile.terrain != Terrain::Water)
+            })
+            .collect();
+        rng.shuffle(&mut sources);
+
+        let river_count = coords.len() / 40 + 1;
+        for source in sources.into_iter().take(river_count) {
+            let mut current = source;
+            for _ in 0..coords.len() {
+                let Some(tile) = tiles.get_mut(current) else {
+                    break;
+                };
+                if tile.terrain == Terrain::Water && !tile.river {
+                    break;
+                }
+                tile.terrain = Terrain::Water;
+                tile.river = true;
+                let elevation = tile.elevation;
+
+                let lowest_neighbor = current
+                    .neighbors()
+                    .filter(|neighbor| neighbor.length() > START_AREA_RADIUS as i32)
+                    .filter_map(|neighbor| Some((neighbor, tiles.get(neighbor)?.elevation)))
+                    .min_by(|(a_coord, a), (b_coord, b)| {
+                        a.total_cmp(b).then_with(|| a_coord.cmp(b_coord))
+                    });
+                match lowest_neighbor {
+                    Some((neighbor, neighbor_elevation)) if neighbor_elevation < elevation => {
+                        current = neighbor;
+                    }
+                    _ => break,
+                }
+            }
+        }
+    }
+
+    /// Scatter deposits over the tiles allowed by each kind's rule, keeping
+    /// them spaced apart and out of the start area.
+    fn add_deposits(
+        &self,
+        tiles: &mut HexMap<GeneratedTile>,
+        coords: &[HexCoord],
+        rng: &mut SplitMix64,
+    ) {
+        let mut placed: Vec<HexCoord> = Vec::new();
+        for rule in DEPOSIT_RULES.iter() {
+            let mut candidates: Vec<_> = coords
+                .iter()
+                .copied()
+                .filter(|coord| {
+                    coord.length() > START_AREA_RADIUS as i32
+                        && tiles
+                            .get(*coord)
+                            .is_some_and(|tile| rule.terrains.contains(&tile.terrain))
+                })
+                .collect();
+            rng.shuffle(&mut candidates);
+
+            let count = (coords.len() / rule.tiles_per_deposit).max(1);
+            let mut remaining = count;
+            for coord in candidates {
+                if remaining == 0 {
+                    break;
+                }
+                if placed
+                    .iter()
+                    .any(|other| other.distance(coord) < rule.min_spacing)
+                {
+                    continue;
+                }
+                if let Some(tile) = tiles.get_mut(coord) {
+                    tile.deposit = Some(rule.deposit);
+                    placed.push(coord);
+                    remaining -= 1;
+                }
+            }
+        }
+    }
+}
+
+/// Pick a terrain type from the elevation and moisture of a tile.
+fn biome(elevation: f32, moisture: f32) -> Terrain {
+    match elevation {
+        e if e < SEA_LEVEL => Terrain::Water,
+        e if e < 0.36 => Terrain::Sand,
+        e if e > 0.72 => Terrain::Rock,
+        _ if moisture > 0.55 => Terrain::Forest,
+        _ => Terrain::Grass,
+    }
+}
+
+/// Several octaves of value noise layered on top of each other, between 0
+/// and 1.
+fn fractal_noise(seed: u64, layer: u64, pos: Vec2) -> f32 {
+    const OCTAVES: u32 = 4;
+    let mut total = 0.;
+    let mut amplitude = 1.;
+    let mut max_total = 0.;
+    for octave in 0..OCTAVES {
+        let frequency = (1 << octave) as f32;
+        total += value_noise(seed, layer * 16 + octave as u64, pos * frequency) * amplitude;
+        max_total += amplitude;
+        amplitude *= 0.5;
+    }
+    total / max_total
+}
+
+/// Smoothly interpolated random values on an integer lattice, between 0 and 1.
+fn value_noise(seed: u64, layer: u64, pos: Vec2) -> f32 {
+    let cell = pos.floor();
+    let Vec2 { x: fx, y: fy } = pos - cell;
+    let (x, y) = (cell.x as i64, cell.y as i64);
+    let corner = |dx: i64, dy: i64| lattice_value(seed, layer, x + dx, y + dy);
+
+    let (sx, sy) = (smoothstep(fx), smoothstep(fy));
+    let top = corner(0, 0) + (corner(1, 0) - corner(0, 0)) * sx;
+    let bottom = corner(0, 1) + (corner(1, 1) - corner(0, 1)) * sx;
+    top + (bottom - top) * sy
+}
+
+fn smoothstep(t: f32) -> f32 {
+    t * t * (3. - 2. * t)
+}
+
+fn lattice_value(seed: u64, layer: u64, x: i64, y: i64) -> f32 {
+    let mut rng = SplitMix64::new(
+        seed ^ layer.wrapping_mul(0x9E37_79B9_7F4A_7C15)
+            ^ (x as u64).wrapping_mul(0xBF58_476D_1CE4_E5B9)
+            ^ (y as u64).wrapping_mul(0x94D0_49BB_1331_11EB),
+    );
+    rng.next_f32()
+}
+
+/// A small, fast random number generator. Implemented here rather than taken
+/// from a crate so that generated maps stay the same across dependency
+/// updates.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A random number between 0 (inclusive) and 1 (exclusive).
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Shuffle a slice in place with the Fisher-Yates algorithm.
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = (self.next_u64() % (i as u64 + 1)) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn generate(seed: u64) -> HexMap<GeneratedTile> {
+        MapGenerator::new(seed, MapShape::Hexagon { radius: 12 }).generate(HexOrientation::FlatTop)
+    }
+
+    fn terrain_counts(map: &HexMap<GeneratedTile>) -> HashMap<Terrain, usize> {
+        let mut counts = HashMap::new();
+        for (_, tile) in map.iter() {
+            *counts.entry(tile.terrain).or_default() += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn same_seed_gives_same_map() {
+        assert_eq!(generate(42), generate(42));
+    }
+
+    #[test]
+    fn different_seeds_give_different_maps() {
+        assert_ne!(generate(42), generate(43));
+    }
+
+    /// Fails whenever generation changes, so that changes to the look of maps
+    /// are made on purpose. Update the counts when they are.
+    #[test]
+    fn terrain_snapshot() {
+        let expected = HashMap::from([
+            (Terrain::Grass, 224),
+            (Terrain::Forest, 97),
+            (Terrain::Water, 70),
+            (Terrain::Rock, 16),
+            (Terrain::Sand, 62),
+        ]);
+        assert_eq!(terrain_counts(&generate(0x5EED)), expected);
+    }
+}
@@ -0,0 +1,395 @@
+use std::{error::Error, fmt};
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    utils::BoxedFuture,
+};
+use bevy_asset_loader::prelude::*;
+
+use crate::{
+    hex_layout::HexOrientation, hex_map::HexMap, map_file::MapFile, map_generation::MapGenerator,
+    offset_coord::OffsetCoord, prelude::*,
+};
+
+pub struct MapShapePlugin;
+
+impl Plugin for MapShapePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<MapMask>()
+            .register_asset_loader(MapMaskLoader)
+            .insert_resource(MapConfig::from_args(std::env::args().skip(1)))
+            .add_collection_to_loading_state::<_, MapAssets>(AppState::Loading);
+    }
+}
+
+/// The overall shape of the board.
+#[derive(Debug, Clone)]
+pub enum MapShape {
+    /// A large hexagon containing every tile within `radius` steps of the
+    /// center tile.
+    Hexagon { radius: u32 },
+    /// A rectangle of tiles `width` columns wide and `height` rows tall, as
+    /// laid out in offset coordinates matching the grid orientation.
+    Rectangle { width: u32, height: u32 },
+    /// A rhombus of tiles `width` tiles along the q axis and `height` tiles
+    /// along the r axis.
+    Parallelogram { width: u32, height: u32 },
+    /// A triangle of tiles with `size` tiles along each side.
+    Triangle { size: u32 },
+    /// Any shape, drawn as a mask of tiles in offset coordinates.
+    Mask(MapMask),
+}
+
+impl Default for MapShape {
+    fn default() -> Self {
+        MapShape::Hexagon { radius: 3 }
+    }
+}
+
+impl MapShape {
+    /// The coordinates of all tiles in this shape, roughly centered around
+    /// `HexCoord::ZERO`.
+    pub fn coords(&self, orientation: HexOrientation) -> Vec<HexCoord> {
+        match self {
+            MapShape::Hexagon { radius } => HexCoord::ZERO.range(*radius).collect(),
+            MapShape::Rectangle { width, height } => {
+                let min = Self::rectangle_min(*width, *height);
+                let kind = orientation.offset_kind();
+                (min.row..min.row + *height as i32)
+                    .flat_map(|row| {
+                        (min.col..min.col + *width as i32)
+                            .map(move |col| OffsetCoord::new(col, row).to_hex(kind))
+                    })
+                    .collect()
+            }
+            MapShape::Parallelogram { width, height } => {
+                let (width, height) = (*width as i32, *height as i32);
+                (-height / 2..height - height / 2)
+                    .flat_map(|r| (-width / 2..width - width / 2).map(move |q| HexCoord::new(q, r)))
+                    .collect()
+            }
+            MapShape::Triangle { size } => {
+                let size = *size as i32;
+                let offset = HexCoord::new(-size / 3, -size / 3);
+                (0..size)
+                    .flat_map(|r| (0..size - r).map(move |q| HexCoord::new(q, r) + offset))
+                    .collect()
+            }
+            MapShape::Mask(mask) => {
+                let min = Self::rectangle_min(mask.width(), mask.height());
+                let kind = orientation.offset_kind();
+                mask.rows
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(row, cells)| {
+                        cells
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, cell)| **cell)
+                            .map(move |(col, _)| {
+                                OffsetCoord::new(min.col + col as i32, min.row + row as i32)
+                                    .to_hex(kind)
+                            })
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// An empty map for the tiles in this shape, with dense storage when the
+    /// shape allows it.
+    pub fn new_map<T>(&self, orientation: HexOrientation) -> HexMap<T> {
+        match self {
+            MapShape::Hexagon { radius } => HexMap::hexagon(HexCoord::ZERO, *radius),
+            MapShape::Rectangle { width, height } => HexMap::rectangle(
+                orientation.offset_kind(),
+                Self::rectangle_min(*width, *height),
+                *width,
+                *height,
+            ),
+            MapShape::Mask(mask) => HexMap::rectangle(
+                orientation.offset_kind(),
+                Self::rectangle_min(mask.width(), mask.height()),
+                mask.width(),
+                mask.height(),
+            ),
+            MapShape::Parallelogram { .. } | MapShape::Triangle { .. } => HexMap::sparse(),
+        }
+    }
+
+    /// The offset coordinates of the first tile in a centered rectangle.
+    fn rectangle_min(width: u32, height: u32) -> OffsetCoord {
+        OffsetCoord::new(-(width as i32) / 2, -(height as i32) / 2)
+    }
+}
+
+/// A board shape drawn as text, with one line per row of tiles in offset
+/// coordinates. `#` marks a tile, while `.` and spaces mark holes:
+///
+/// ```text
+/// .###.
+/// #####
+/// ##.##
+/// .###.
+/// ```
+#[derive(Asset, TypePath, Debug, Clone, PartialEq)]
+pub struct MapMask {
+    rows: Vec<Vec<bool>>,
+}
+
+impl MapMask {
+    pub fn parse(text: &str) -> Result<Self, MapMaskError> {
+        let mut rows = text
+            .lines()
+            .enumerate()
+            .map(|(line, cells)| {
+                cells
+                    .trim_end()
+                    .chars()
+                    .enumerate()
+                    .map(|(column, character)| match character {
+                        '#' => Ok(true),
+                        '.' | ' ' => Ok(false),
+                        _ => Err(MapMaskError::InvalidCharacter {
+                            line: line + 1,
+                            column: column + 1,
+                            character,
+                        }),
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        while rows.last().is_some_and(|row| row.is_empty()) {
+            rows.pop();
+        }
+        if !rows.iter().flatten().any(|cell| *cell) {
+            return Err(MapMaskError::Empty);
+        }
+
+        Ok(Self { rows })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.rows.iter().map(Vec::len).max().unwrap_or_default() as u32
+    }
+
+    pub fn height(&self) -> u32 {
+        self.rows.len() as u32
+    }
+}
+
+#[derive(Debug)]
+pub enum MapMaskError {
+    Io(std::io::Error),
+    InvalidCharacter {
+        line: usize,
+        column: usize,
+        character: char,
+    },
+    Empty,
+}
+
+impl fmt::Display for MapMaskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapMaskError::Io(error) => write!(f, "could not read map mask: {error}"),
+            MapMaskError::InvalidCharacter {
+                line,
+                column,
+                character,
+            } => write!(
+                f,
+                "invalid character {character:?} at line {line}, column {column}, expected '#', '.' or ' '"
+            ),
+            MapMaskError::Empty => write!(f, "map mask contains no tiles"),
+        }
+    }
+}
+
+impl Error for MapMaskError {}
+
+impl From<std::io::Error> for MapMaskError {
+    fn from(error: std::io::Error) -> Self {
+        MapMaskError::Io(error)
+    }
+}
+
+/// Loads `MapMask` assets from `.mask` files.
+struct MapMaskLoader;
+
+impl AssetLoader for MapMaskLoader {
+    type Asset = MapMask;
+    type Settings = ();
+    type Error = MapMaskError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<MapMask, MapMaskError>> {
+        Box::pin(async move {
+            let mut text = String::new();
+            reader.read_to_string(&mut text).await?;
+            MapMask::parse(&text)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["mask"]
+    }
+}
+
+/// Which board to generate, picked at startup from the command line:
+///
+/// - `--seed <number>` seeds the map generator.
+/// - `--map <shape>` picks the shape of the board, as one of
+///   `hexagon:<radius>`, `rectangle:<width>x<height>`,
+///   `parallelogram:<width>x<height>`, `triangle:<size>` or the asset path of
+///   a `.mask` file, such as `maps/island.mask`. Hand-authored maps are loaded
+///   from the asset path of a `.map.ron` or `.map.json` file instead.
+#[derive(Resource, Debug, Clone)]
+pub struct MapConfig {
+    pub seed: u64,
+    pub source: MapSource,
+}
+
+#[derive(Debug, Clone)]
+pub enum MapSource {
+    /// A map generated in one of the shapes defined in code.
+    Preset(MapShape),
+    /// A map generated in the shape of the mask loaded from the asset at the
+    /// given path.
+    Mask(String),
+    /// The hand-authored map loaded from the asset at the given path.
+    File(String),
+}
+
+impl Default for MapConfig {
+    fn default() -> Self {
+        let map_generator = MapGenerator::default();
+        Self {
+            seed: map_generator.seed,
+            source: MapSource::Preset(map_generator.shape),
+        }
+    }
+}
+
+impl MapConfig {
+    pub fn from_args(mut args: impl Iterator<Item = String>) -> Self {
+        let mut config = Self::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--seed" => match args.next().map(|seed| seed.parse()) {
+                    Some(Ok(seed)) => config.seed = seed,
+                    _ => warn!("Ignoring invalid --seed, expected a number"),
+                },
+                "--map" => match args.next().as_deref().map(MapSource::parse) {
+                    Some(Some(source)) => config.source = source,
+                    _ => warn!(
+                        "Ignoring invalid --map, expected hexagon:<radius>, \
+                        rectangle:<width>x<height>, parallelogram:<width>x<height>, \
+                        triangle:<size> or the path of a .mask, .map.ron or .map.json asset"
+                    ),
+                },
+                _ => {}
+            }
+        }
+        config
+    }
+}
+
+impl MapSource {
+    fn parse(spec: &str) -> Option<Self> {
+        if spec.ends_with(".mask") {
+            return Some(MapSource::Mask(spec.to_string()));
+        }
+        if spec.ends_with(".map.ron") || spec.ends_with(".map.json") {
+            return Some(MapSource::File(spec.to_string()));
+        }
+
+        let (kind, size) = spec.split_once(':')?;
+        let parse_size = || -> Option<(u32, u32)> {
+            let (width, height) = size.split_once('x')?;
+            Some((width.parse().ok()?, height.parse().ok()?))
+        };
+        let shape = match kind {
+            "hexagon" => MapShape::Hexagon {
+                radius: size.parse().ok()?,
+            },
+            "rectangle" => {
+                let (width, height) = parse_size()?;
+                MapShape::Rectangle { width, height }
+            }
+            "parallelogram" => {
+                let (width, height) = parse_size()?;
+                MapShape::Parallelogram { width, height }
+            }
+            "triangle" => MapShape::Triangle {
+                size: size.parse().ok()?,
+            },
+            _ => return None,
+        };
+        Some(MapSource::Preset(shape))
+    }
+}
+
+/// The assets needed by the configured map, loaded during `AppState::Loading`.
+#[derive(Resource)]
+pub struct MapAssets {
+    pub mask: Option<Handle<MapMask>>,
+    pub file: Option<Handle<MapFile>>,
+}
+
+impl AssetCollection for MapAssets {
+    fn create(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        match &world.resource::<MapConfig>().source {
+            MapSource::Preset(_) => Self {
+                mask: None,
+                file: None,
+            },
+            MapSource::Mask(path) => Self {
+                mask: Some(asset_server.load(path)),
+                file: None,
+            },
+            MapSource::File(path) => Self {
+                mask: None,
+                file: Some(asset_server.load(path)),
+            },
+        }
+    }
+
+    fn load(world: &mut World) -> Vec<UntypedHandle> {
+        let Self { mask, file } = Self::create(world);
+        mask.map(Handle::untyped)
+            .into_iter()
+            .chain(file.map(Handle::untyped))
+            .collect()
+    }
+}
+
+/// Sets up the map generator for the board picked in the `MapConfig`.
+pub fn configure_map_generator(
+    mut map_generator: ResMut<MapGenerator>,
+    config: Res<MapConfig>,
+    map_assets: Res<MapAssets>,
+    masks: Res<Assets<MapMask>>,
+) {
+    let shape = match &config.source {
+        MapSource::Preset(shape) => shape.clone(),
+        MapSource::Mask(path) => match map_assets.mask.as_ref().and_then(|mask| masks.get(mask)) {
+            Some(mask) => MapShape::Mask(mask.clone()),
+            None => {
+                warn!("Missing map mask {path:?}, falling back to the default shape");
+                MapShape::default()
+            }
+        },
+        // Hand-authored maps aren't generated at all
+        MapSource::File(_) => MapShape::default(),
+    };
+
+    *map_generator = MapGenerator::new(config.seed, shape);
+}
@@ -1,4 +1,4 @@
-use crate::{camera::MainCamera, prelude::*};
+use crate::{camera::MainCamera, elevation::TileHeights, prelude::*};
 use bevy::prelude::*;
 
 pub struct MousePositionPlugin;
@@ -33,6 +33,8 @@ pub fn update_mouse_position(
     camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
     window_query: Query<&Window>,
     mut mouse_position: ResMut<MousePosition>,
+    layout: Res<HexLayout>,
+    tile_heights: TileHeights,
 ) {
     let (camera, camera_transform) = camera_query.single();
     let window = window_query.single();
@@ -41,18 +43,25 @@ pub fn update_mouse_position(
         return;
     };
 
-    let Some(world_position) = camera
-        .viewport_to_world(camera_transform, viewport_position)
-        .and_then(|world_ray| {
-            world_ray
+    let Some(world_ray) = camera.viewport_to_world(camera_transform, viewport_position) else {
+        return;
+    };
+
+    // Prefer the tile hit by the ray, which may be raised above the ground,
+    // and fall back to the ground plane when pointing outside the board
+    let (world_position, tile_position) = match tile_heights.raycast(world_ray, &layout) {
+        Some((coord, hit)) => (hit, TilePosition::from(coord)),
+        None => {
+            let Some(world_position) = world_ray
                 .intersect_plane(Vec3::ZERO, Vec3::Y)
                 .map(|distance| world_ray.get_point(distance))
-        })
-    else {
-        return;
+            else {
+                return;
+            };
+            (world_position, layout.vec3_to_tile(world_position))
+        }
     };
 
-    let tile_position = TilePosition::from(world_position);
     let current_mouse_position = MousePosition {
         viewport: viewport_position,
         world: world_position,
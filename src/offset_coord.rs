@@ -0,0 +1,105 @@
+use crate::prelude::*;
+
+/// Which rows or columns are shoved over by half a tile in an offset
+/// coordinate system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OffsetKind {
+    /// Every odd row is shoved over by half a tile. Used with pointy-top
+    /// layouts.
+    OddR,
+    /// Every even row is shoved over by half a tile. Used with pointy-top
+    /// layouts.
+    EvenR,
+    /// Every odd column is shoved over by half a tile. Used with flat-top
+    /// layouts.
+    OddQ,
+    /// Every even column is shoved over by half a tile. Used with flat-top
+    /// layouts.
+    EvenQ,
+}
+
+/// A position in a rectangular grid of hexagons, as used by spreadsheets and
+/// image editors. Only meaningful together with an `OffsetKind`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OffsetCoord {
+    pub col: i32,
+    pub row: i32,
+}
+
+impl OffsetCoord {
+    pub const fn new(col: i32, row: i32) -> Self {
+        Self { col, row }
+    }
+
+    /// Convert a tile coordinate into offset coordinates of the given kind.
+    pub fn from_hex(HexCoord { q, r }: HexCoord, kind: OffsetKind) -> Self {
+        match kind {
+            OffsetKind::OddR => Self::new(q + (r - (r & 1)) / 2, r),
+            OffsetKind::EvenR => Self::new(q + (r + (r & 1)) / 2, r),
+            OffsetKind::OddQ => Self::new(q, r + (q - (q & 1)) / 2),
+            OffsetKind::EvenQ => Self::new(q, r + (q + (q & 1)) / 2),
+        }
+    }
+
+    /// Convert these offset coordinates of the given kind into a tile
+    /// coordinate.
+    pub fn to_hex(self, kind: OffsetKind) -> HexCoord {
+        let Self { col, row } = self;
+        match kind {
+            OffsetKind::OddR => HexCoord::new(col - (row - (row & 1)) / 2, row),
+            OffsetKind::EvenR => HexCoord::new(col - (row + (row & 1)) / 2, row),
+            OffsetKind::OddQ => HexCoord::new(col, row - (col - (col & 1)) / 2),
+            OffsetKind::EvenQ => HexCoord::new(col, row - (col + (col & 1)) / 2),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KINDS: [OffsetKind; 4] = [
+        OffsetKind::OddR,
+        OffsetKind::EvenR,
+        OffsetKind::OddQ,
+        OffsetKind::EvenQ,
+    ];
+
+    #[test]
+    fn tile_coordinates_survive_a_round_trip() {
+        for kind in KINDS {
+            for coord in HexCoord::ZERO.range(6) {
+                assert_eq!(OffsetCoord::from_hex(coord, kind).to_hex(kind), coord);
+            }
+        }
+    }
+
+    #[test]
+    fn offset_coordinates_survive_a_round_trip() {
+        for kind in KINDS {
+            for col in -5..=5 {
+                for row in -5..=5 {
+                    let offset = OffsetCoord::new(col, row);
+                    assert_eq!(OffsetCoord::from_hex(offset.to_hex(kind), kind), offset);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rows_and_columns_are_shoved_by_parity() {
+        // Row and column -1 are odd, so they're shoved over by half a tile in
+        // odd-r and odd-q, but not in even-r and even-q
+        let first_of_row = |row, kind| OffsetCoord::new(0, row).to_hex(kind);
+        assert_eq!(first_of_row(-1, OffsetKind::OddR), HexCoord::new(1, -1));
+        assert_eq!(first_of_row(-1, OffsetKind::EvenR), HexCoord::new(0, -1));
+        assert_eq!(
+            OffsetCoord::new(-1, 0).to_hex(OffsetKind::OddQ),
+            HexCoord::new(-1, 1)
+        );
+        assert_eq!(
+            OffsetCoord::new(-1, 0).to_hex(OffsetKind::EvenQ),
+            HexCoord::new(-1, 0)
+        );
+    }
+}
@@ -0,0 +1,248 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+use crate::{hex_map::HexMap, prelude::*, terrain::Terrain};
+
+/// The cheapest possible cost of entering a tile. Movement costs below this
+/// would make the A* heuristic overestimate, so they are raised to it.
+pub const MIN_MOVEMENT_COST: u32 = 1;
+
+/// A path between two tiles, including both ends.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path {
+    pub tiles: Vec<HexCoord>,
+    /// The sum of the movement costs of every tile entered along the path.
+    pub cost: u32,
+}
+
+/// The cost of walking onto a tile with the given terrain, or `None` if it
+/// can't be walked on: when there is no tile, when its terrain is impassable,
+/// or when a structure stands on it.
+pub fn walking_cost(terrain: Option<Terrain>, occupied: bool) -> Option<u32> {
+    if occupied {
+        return None;
+    }
+    terrain?.movement_cost()
+}
+
+/// The cost of stepping onto a tile, given the cost function supplied by the
+/// caller. Goal tiles can always be entered, even when impassable, so that
+/// paths can lead to occupied tiles such as the site of a structure.
+fn step_cost(
+    movement_cost: &impl Fn(HexCoord) -> Option<u32>,
+    coord: HexCoord,
+    is_goal: bool,
+) -> Option<u32> {
+    match movement_cost(coord) {
+        Some(cost) => Some(cost.max(MIN_MOVEMENT_COST)),
+        None if is_goal => Some(MIN_MOVEMENT_COST),
+        None => None,
+    }
+}
+
+/// Find the cheapest path from `start` to `goal` using A*, with hex distance
+/// as the heuristic.
+///
+/// `movement_cost` returns the cost of entering a tile, or `None` if the tile
+/// is impassable or doesn't exist, such as one built on `walking_cost`.
+/// Returns `None` if there is no path.
+pub fn find_path(
+    start: HexCoord,
+    goal: HexCoord,
+    movement_cost: impl Fn(HexCoord) -> Option<u32>,
+) -> Option<Path> {
+    let mut open = BinaryHeap::from([Reverse((0, start))]);
+    let mut came_from = HashMap::new();
+    let mut costs = HashMap::from([(start, 0)]);
+
+    while let Some(Reverse((_, current))) = open.pop() {
+        if current == goal {
+            let mut tiles = vec![goal];
+            while let Some(previous) = came_from.get(tiles.last()?) {
+                tiles.push(*previous);
+            }
+            tiles.reverse();
+            return Some(Path {
+                tiles,
+                cost: costs[&goal],
+            });
+        }
+
+        let current_cost = costs[&current];
+        for neighbor in current.neighbors() {
+            let Some(step) = step_cost(&movement_cost, neighbor, neighbor == goal) else {
+                continue;
+            };
+            let cost = current_cost + step;
+            if costs.get(&neighbor).is_some_and(|known| *known <= cost) {
+                continue;
+            }
+            costs.insert(neighbor, cost);
+            came_from.insert(neighbor, current);
+            let estimate = cost + neighbor.distance(goal) as u32 * MIN_MOVEMENT_COST;
+            open.push(Reverse((estimate, neighbor)));
+        }
+    }
+
+    None
+}
+
+/// The cheapest way from every reachable tile to the nearest of a set of
+/// goals, computed once with multi-source Dijkstra so that any number of
+/// agents can share it.
+#[derive(Debug, Clone)]
+pub struct FlowField {
+    /// The cost of reaching the nearest goal, and the next tile to step onto
+    /// to get there (`None` for the goals themselves).
+    tiles: HexMap<(u32, Option<HexCoord>)>,
+}
+
+impl FlowField {
+    /// Compute a flow field towards `goals`.
+    ///
+    /// `movement_cost` returns the cost of entering a tile, or `None` if the
+    /// tile is impassable or doesn't exist. It must return `None` outside the
+    /// map, or the search will never end.
+    pub fn new(
+        goals: impl IntoIterator<Item = HexCoord>,
+        movement_cost: impl Fn(HexCoord) -> Option<u32>,
+    ) -> Self {
+        let mut tiles = HexMap::sparse();
+        let mut open = BinaryHeap::new();
+        for goal in goals {
+            tiles.insert(goal, (0, None));
+            open.push(Reverse((0, goal)));
+        }
+
+        while let Some(Reverse((cost, current))) = open.pop() {
+            if tiles.get(current).is_some_and(|(known, _)| *known < cost) {
+                continue;
+            }
+
+            // Agents move against the direction of the search, so stepping
+            // from a neighbor onto the current tile costs the current tile's
+            // movement cost
+            let is_goal = tiles.get(current).is_some_and(|(_, next)| next.is_none());
+            let Some(step) = step_cost(&movement_cost, current, is_goal) else {
+                continue;
+            };
+            for neighbor in current.neighbors() {
+                if movement_cost(neighbor).is_none() {
+                    continue;
+                }
+                let neighbor_cost = cost + step;
+                if tiles
+                    .get(neighbor)
+                    .is_some_and(|(known, _)| *known <= neighbor_cost)
+                {
+                    continue;
+                }
+                tiles.insert(neighbor, (neighbor_cost, Some(current)));
+                open.push(Reverse((neighbor_cost, neighbor)));
+            }
+        }
+
+        Self { tiles }
+    }
+
+    /// Whether the nearest goal can be reached from the given tile.
+    pub fn is_reachable(&self, from: HexCoord) -> bool {
+        self.tiles.contains(from)
+    }
+
+    /// The total movement cost of reaching the nearest goal from the given
+    /// tile, or `None` if no goal can be reached.
+    pub fn cost(&self, from: HexCoord) -> Option<u32> {
+        self.tiles.get(from).map(|(cost, _)| *cost)
+    }
+
+    /// The tile to step onto next from the given tile to get closer to the
+    /// nearest goal. `None` if the tile is a goal or no goal can be reached.
+    pub fn next_step(&self, from: HexCoord) -> Option<HexCoord> {
+        self.tiles.get(from)?.1
+    }
+
+    /// The full path from the given tile to the nearest goal, or `None` if no
+    /// goal can be reached.
+    pub fn path(&self, from: HexCoord) -> Option<Path> {
+        let cost = self.cost(from)?;
+        let mut tiles = vec![from];
+        while let Some(next) = self.next_step(*tiles.last()?) {
+            tiles.push(next);
+        }
+        Some(Path { tiles, cost })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    /// A hexagon of grass around the origin, with structures on `occupied`.
+    struct Board {
+        terrain: HexMap<Terrain>,
+        occupied: HashSet<HexCoord>,
+    }
+
+    impl Board {
+        fn new(radius: u32) -> Self {
+            let mut terrain = HexMap::hexagon(HexCoord::ZERO, radius);
+            for coord in HexCoord::ZERO.range(radius) {
+                terrain.insert(coord, Terrain::Grass);
+            }
+            Self {
+                terrain,
+                occupied: HashSet::new(),
+            }
+        }
+
+        fn movement_cost(&self, coord: HexCoord) -> Option<u32> {
+            walking_cost(
+                self.terrain.get(coord).copied(),
+                self.occupied.contains(&coord),
+            )
+        }
+    }
+
+    #[test]
+    fn paths_detour_around_structures() {
+        let mut board = Board::new(3);
+        let start = HexCoord::new(-1, 0);
+        let goal = HexCoord::new(1, 0);
+        board.occupied.insert(HexCoord::ZERO);
+
+        let path = find_path(start, goal, |coord| board.movement_cost(coord)).unwrap();
+        assert_eq!(path.tiles.first(), Some(&start));
+        assert_eq!(path.tiles.last(), Some(&goal));
+        assert!(!path.tiles.contains(&HexCoord::ZERO));
+        assert_eq!(path.cost, 3);
+
+        let flow_field = FlowField::new([goal], |coord| board.movement_cost(coord));
+        let flow_path = flow_field.path(start).unwrap();
+        assert!(!flow_path.tiles.contains(&HexCoord::ZERO));
+        assert_eq!(flow_path.cost, 3);
+    }
+
+    #[test]
+    fn goals_cut_off_by_water_are_unreachable() {
+        let mut board = Board::new(3);
+        let start = HexCoord::new(-2, 0);
+        let goal = HexCoord::new(1, 0);
+        for neighbor in goal.neighbors() {
+            board.terrain.insert(neighbor, Terrain::Water);
+        }
+
+        assert_eq!(
+            find_path(start, goal, |coord| board.movement_cost(coord)),
+            None
+        );
+
+        let flow_field = FlowField::new([goal], |coord| board.movement_cost(coord));
+        assert!(!flow_field.is_reachable(start));
+        assert_eq!(flow_field.path(start), None);
+    }
+}
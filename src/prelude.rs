@@ -1 +1,4 @@
-pub use crate::{mouse_position::MousePosition, state::AppState, tile_position::TilePosition};
+pub use crate::{
+    hex_coord::HexCoord, hex_layout::HexLayout, mouse_position::MousePosition, state::AppState,
+    tile_position::TilePosition,
+};
@@ -0,0 +1,361 @@
+#![allow(clippy::type_complexity)]
+
+use std::{error::Error, fmt, fs, io, path::Path, time::Duration};
+
+use bevy::prelude::*;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    clock::GameClock,
+    elevation::Elevation,
+    map_file::MapFileTile,
+    map_generation::Deposit,
+    prelude::*,
+    stockpile::{Goods, Stockpile, STARTING_GOODS},
+    structure::{
+        planned_structure::{PlannedStructure, ReservedGoods},
+        Facing, StructureId,
+    },
+    terrain::Terrain,
+    tiles::Tile,
+};
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(LoadedSave::from_args(std::env::args().skip(1)))
+            .add_systems(
+                OnExit(AppState::Loading),
+                (restore_game_clock, restore_stockpile),
+            )
+            .add_systems(OnEnter(AppState::InGame), clear_loaded_save)
+            .add_systems(
+                Update,
+                (quick_save, quick_load).run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+/// The version of the save format written by this build. Bump it whenever
+/// `SaveGame` changes in a way that older saves can't be read as, and add a
+/// migration from the previous version to `SaveGame::from_ron`.
+pub const SAVE_VERSION: u32 = 2;
+
+const QUICK_SAVE_PATH: &str = "saves/quicksave.ron";
+const QUICK_SAVE_KEY: KeyCode = KeyCode::F5;
+const QUICK_LOAD_KEY: KeyCode = KeyCode::F9;
+
+/// Everything needed to restore a game exactly as it was saved.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SaveGame {
+    pub version: u32,
+    pub clock: Duration,
+    pub tiles: Vec<MapFileTile>,
+    pub structures: Vec<SavedStructure>,
+    /// Saves from before there was a stockpile start out with the goods of a
+    /// new game.
+    #[serde(default = "starting_goods")]
+    pub stockpile: Goods,
+}
+
+fn starting_goods() -> Goods {
+    STARTING_GOODS
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedStructure {
+    pub structure: StructureId,
+    pub coord: HexCoord,
+    /// Saves from before structures could be turned have no facing, as they
+    /// all faced the default direction.
+    #[serde(default)]
+    pub facing: Facing,
+}
+
+/// Version 1 saves, from before structures were defined in data files, when
+/// every kind of structure was a variant of an enum.
+#[derive(Deserialize)]
+struct SaveGameV1 {
+    clock: Duration,
+    tiles: Vec<MapFileTile>,
+    structures: Vec<SavedStructureV1>,
+}
+
+#[derive(Deserialize)]
+struct SavedStructureV1 {
+    structure: StructureTypeV1,
+    coord: HexCoord,
+    #[serde(default)]
+    facing: Facing,
+}
+
+#[derive(Deserialize)]
+enum StructureTypeV1 {
+    House,
+}
+
+impl From<SaveGameV1> for SaveGame {
+    fn from(save: SaveGameV1) -> Self {
+        Self {
+            version: SAVE_VERSION,
+            clock: save.clock,
+            tiles: save.tiles,
+            structures: save
+                .structures
+                .into_iter()
+                .map(|saved| SavedStructure {
+                    structure: match saved.structure {
+                        StructureTypeV1::House => StructureId::new("house"),
+                    },
+                    coord: saved.coord,
+                    facing: saved.facing,
+                })
+                .collect(),
+            stockpile: STARTING_GOODS,
+        }
+    }
+}
+
+/// Just enough of a save to tell which version of the format it's in.
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+impl SaveGame {
+    pub fn from_ron(text: &str) -> Result<Self, SaveError> {
+        let SaveHeader { version } = ron::from_str(text)?;
+        match version {
+            SAVE_VERSION => Ok(ron::from_str(text)?),
+            1 => Ok(ron::from_str::<SaveGameV1>(text)?.into()),
+            // Saves from older versions are read as the types of their own
+            // version here, and migrated one version at a time up to the
+            // current one
+            _ => Err(SaveError::UnsupportedVersion(version)),
+        }
+    }
+
+    pub fn to_ron(&self) -> Result<String, SaveError> {
+        Ok(ron::ser::to_string_pretty(self, PrettyConfig::default())?)
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, SaveError> {
+        Self::from_ron(&fs::read_to_string(path)?)
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), SaveError> {
+        let path = path.as_ref();
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        Ok(fs::write(path, self.to_ron()?)?)
+    }
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    Parse(ron::error::SpannedError),
+    Serialize(ron::Error),
+    /// The save was written by a newer build, or an older one that can no
+    /// longer be migrated.
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(error) => write!(f, "{error}"),
+            SaveError::Parse(error) => write!(
+                f,
+                "{}:{}: {}",
+                error.position.line, error.position.col, error.code
+            ),
+            SaveError::Serialize(error) => write!(f, "{error}"),
+            SaveError::UnsupportedVersion(version) => write!(
+                f,
+                "unsupported save version {version}, this build reads version {SAVE_VERSION}"
+            ),
+        }
+    }
+}
+
+impl Error for SaveError {}
+
+impl From<io::Error> for SaveError {
+    fn from(error: io::Error) -> Self {
+        SaveError::Io(error)
+    }
+}
+
+impl From<ron::error::SpannedError> for SaveError {
+    fn from(error: ron::error::SpannedError) -> Self {
+        SaveError::Parse(error)
+    }
+}
+
+impl From<ron::Error> for SaveError {
+    fn from(error: ron::Error) -> Self {
+        SaveError::Serialize(error)
+    }
+}
+
+/// The save to restore the next time the game leaves `AppState::Loading`,
+/// instead of generating a new map.
+#[derive(Resource, Default)]
+pub struct LoadedSave(pub Option<SaveGame>);
+
+impl LoadedSave {
+    /// Load the save given with `--load <path>` on the command line, if any.
+    fn from_args(mut args: impl Iterator<Item = String>) -> Self {
+        while let Some(arg) = args.next() {
+            if arg != "--load" {
+                continue;
+            }
+            let Some(path) = args.next() else {
+                warn!("Ignoring --load without a save path");
+                break;
+            };
+            match SaveGame::read(&path) {
+                Ok(save) => return Self(Some(save)),
+                Err(error) => error!("Could not load save {path:?}: {error}"),
+            }
+        }
+        Self(None)
+    }
+}
+
+fn restore_game_clock(mut clock: ResMut<GameClock>, loaded_save: Res<LoadedSave>) {
+    if let Some(save) = &loaded_save.0 {
+        clock.elapsed = save.clock;
+    }
+}
+
+fn restore_stockpile(mut stockpile: ResMut<Stockpile>, loaded_save: Res<LoadedSave>) {
+    if let Some(save) = &loaded_save.0 {
+        *stockpile = Stockpile::new(save.stockpile);
+    }
+}
+
+fn clear_loaded_save(mut loaded_save: ResMut<LoadedSave>) {
+    loaded_save.0 = None;
+}
+
+fn quick_save(
+    keyboard_input: Res<Input<KeyCode>>,
+    tile_query: Query<(&HexCoord, &Terrain, Option<&Elevation>, Option<&Deposit>), With<Tile>>,
+    structure_query: Query<(&StructureId, &HexCoord, &Facing), Without<PlannedStructure>>,
+    reserved_goods_query: Query<&ReservedGoods>,
+    clock: Res<GameClock>,
+    stockpile: Res<Stockpile>,
+) {
+    if !keyboard_input.just_pressed(QUICK_SAVE_KEY) {
+        return;
+    }
+
+    // Planned structures aren't saved, so the goods set aside for them go back
+    // into the saved stockpile
+    let mut stockpile = *stockpile;
+    for reserved_goods in reserved_goods_query.iter() {
+        stockpile.add(&reserved_goods.0);
+    }
+
+    let save = SaveGame {
+        version: SAVE_VERSION,
+        clock: clock.elapsed,
+        tiles: tile_query
+            .iter()
+            .map(|(coord, terrain, elevation, deposit)| MapFileTile {
+                coord: *coord,
+                terrain: *terrain,
+                elevation: elevation.copied().unwrap_or_default(),
+                deposit: deposit.copied(),
+            })
+            .collect(),
+        structures: structure_query
+            .iter()
+            .map(|(structure, coord, facing)| SavedStructure {
+                structure: structure.clone(),
+                coord: *coord,
+                facing: *facing,
+            })
+            .collect(),
+        stockpile: *stockpile.goods(),
+    };
+
+    match save.write(QUICK_SAVE_PATH) {
+        Ok(()) => info!("Saved game to {QUICK_SAVE_PATH:?}"),
+        Err(error) => error!("Could not save game to {QUICK_SAVE_PATH:?}: {error}"),
+    }
+}
+
+/// Restores the quick-save by going back through `AppState::Loading`, which
+/// despawns the current game and spawns the saved one in its place.
+fn quick_load(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut loaded_save: ResMut<LoadedSave>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if !keyboard_input.just_pressed(QUICK_LOAD_KEY) {
+        return;
+    }
+
+    match SaveGame::read(QUICK_SAVE_PATH) {
+        Ok(save) => {
+            loaded_save.0 = Some(save);
+            next_state.set(AppState::Loading);
+        }
+        Err(error) => error!("Could not load save {QUICK_SAVE_PATH:?}: {error}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_version_1_saves() {
+        let text = r#"(
+            version: 1,
+            clock: (secs: 90, nanos: 0),
+            tiles: [
+                (coord: (q: 0, r: 0), terrain: Grass, elevation: 1),
+            ],
+            structures: [
+                (structure: House, coord: (q: 0, r: 0), facing: 2),
+                (structure: House, coord: (q: 1, r: -1)),
+            ],
+        )"#;
+
+        let save = SaveGame::from_ron(text).unwrap();
+
+        assert_eq!(
+            save,
+            SaveGame {
+                version: SAVE_VERSION,
+                clock: Duration::from_secs(90),
+                tiles: vec![MapFileTile {
+                    coord: HexCoord::new(0, 0),
+                    terrain: Terrain::Grass,
+                    elevation: Elevation(1),
+                    deposit: None,
+                }],
+                structures: vec![
+                    SavedStructure {
+                        structure: StructureId::new("house"),
+                        coord: HexCoord::new(0, 0),
+                        facing: Facing::new(2),
+                    },
+                    SavedStructure {
+                        structure: StructureId::new("house"),
+                        coord: HexCoord::new(1, -1),
+                        facing: Facing::default(),
+                    },
+                ],
+                stockpile: STARTING_GOODS,
+            }
+        );
+    }
+}
@@ -0,0 +1,175 @@
+#![allow(clippy::type_complexity)]
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    elevation::Elevation,
+    prelude::*,
+    structure::{occupancy::Occupancy, registry::StructureRegistry, StructureId},
+    terrain::Terrain,
+    tiles::{Tile, TileIndex},
+};
+use bevy::prelude::*;
+
+pub struct SightPlugin;
+
+impl Plugin for SightPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, update_viewsheds.run_if(in_state(AppState::InGame)));
+    }
+}
+
+/// The set of tiles an entity can see from its `HexCoord`. Kept up to date by
+/// `update_viewsheds` whenever the entity moves or the board changes.
+///
+/// Sight is blocked by tiles whose terrain blocks it, such as rock, by tiles
+/// covered by tall structures, and by tiles raised higher than the one the
+/// entity stands on.
+#[derive(Component, Debug, Default)]
+pub struct Viewshed {
+    pub radius: u32,
+    pub visible: HashSet<HexCoord>,
+}
+
+/// Whether `to` can be seen from `from`, i.e. whether a straight line between
+/// them passes through no blocking tiles. The end points themselves never
+/// block, so blocking tiles can be seen but not seen through.
+///
+/// Lines running exactly along tile edges are traced on both sides of the
+/// edge, and sight is only blocked if both sides are blocked.
+pub fn has_line_of_sight(
+    from: HexCoord,
+    to: HexCoord,
+    is_blocking: impl Fn(HexCoord) -> bool,
+) -> bool {
+    let TilePosition { q, r } = HexCoord::LINE_NUDGE;
+    [TilePosition::new(q, r), TilePosition::new(-q, -r)]
+        .into_iter()
+        .any(|nudge| {
+            from.line_to_with_nudge(to, nudge)
+                .all(|coord| coord == from || coord == to || !is_blocking(coord))
+        })
+}
+
+/// All tiles within `radius` steps of `origin` that can be seen from it.
+pub fn field_of_view(
+    origin: HexCoord,
+    radius: u32,
+    is_blocking: impl Fn(HexCoord) -> bool,
+) -> HashSet<HexCoord> {
+    origin
+        .range(radius)
+        .filter(|coord| has_line_of_sight(origin, *coord, &is_blocking))
+        .collect()
+}
+
+fn update_viewsheds(
+    mut viewer_query: Query<(Ref<HexCoord>, &mut Viewshed)>,
+    tile_query: Query<(&HexCoord, &Terrain, &Elevation), With<Tile>>,
+    changed_tile_query: Query<
+        (),
+        (
+            With<Tile>,
+            Or<(Changed<HexCoord>, Changed<Terrain>, Changed<Elevation>)>,
+        ),
+    >,
+    structure_query: Query<&StructureId>,
+    tile_index: Res<TileIndex>,
+    occupancy: Res<Occupancy>,
+    registry: Res<StructureRegistry>,
+) {
+    let board_changed =
+        tile_index.is_changed() || occupancy.is_changed() || !changed_tile_query.is_empty();
+
+    let elevations: HashMap<HexCoord, Elevation> = tile_query
+        .iter()
+        .map(|(coord, _, elevation)| (*coord, *elevation))
+        .collect();
+    let tall_structure_tiles = occupancy.iter().filter_map(|(coord, entity)| {
+        let id = structure_query.get(entity).ok()?;
+        registry
+            .get(id)
+            .is_some_and(|definition| definition.blocks_sight)
+            .then_some(coord)
+    });
+    let blocking_tiles: HashSet<HexCoord> = tile_query
+        .iter()
+        .filter(|(_, terrain, _)| terrain.blocks_sight())
+        .map(|(coord, _, _)| *coord)
+        .chain(tall_structure_tiles)
+        .collect();
+
+    for (coord, mut viewshed) in viewer_query.iter_mut() {
+        if !board_changed && !coord.is_changed() && !viewshed.is_added() {
+            continue;
+        }
+
+        let eye_elevation = elevations.get(&coord).copied().unwrap_or_default();
+        let visible = field_of_view(*coord, viewshed.radius, |tile| {
+            blocking_tiles.contains(&tile)
+                || elevations
+                    .get(&tile)
+                    .is_some_and(|elevation| *elevation > eye_elevation)
+        });
+        viewshed.visible = visible
+            .into_iter()
+            .filter(|tile| tile_index.contains(*tile))
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocking_tiles_hide_what_is_behind_them() {
+        let blocker = HexCoord::new(1, 0);
+        let is_blocking = |coord| coord == blocker;
+
+        assert!(!has_line_of_sight(
+            HexCoord::ZERO,
+            HexCoord::new(2, 0),
+            is_blocking
+        ));
+        assert!(has_line_of_sight(HexCoord::ZERO, blocker, is_blocking));
+        assert!(has_line_of_sight(
+            HexCoord::ZERO,
+            HexCoord::new(0, 2),
+            is_blocking
+        ));
+    }
+
+    #[test]
+    fn lines_along_edges_are_blocked_only_on_both_sides() {
+        // The line to (1, 1) runs along the edge between (1, 0) and (0, 1)
+        let target = HexCoord::new(1, 1);
+        let (left, right) = (HexCoord::new(1, 0), HexCoord::new(0, 1));
+
+        assert!(has_line_of_sight(HexCoord::ZERO, target, |coord| coord == left));
+        assert!(has_line_of_sight(HexCoord::ZERO, target, |coord| coord == right));
+        assert!(!has_line_of_sight(HexCoord::ZERO, target, |coord| {
+            coord == left || coord == right
+        }));
+    }
+
+    #[test]
+    fn field_of_view_stops_at_its_radius() {
+        let origin = HexCoord::new(3, -1);
+        let visible = field_of_view(origin, 2, |_| false);
+
+        assert_eq!(visible.len(), 19);
+        assert!(visible.iter().all(|coord| coord.distance(origin) <= 2));
+    }
+
+    #[test]
+    fn field_of_view_leaves_out_shadowed_tiles() {
+        let blocker = HexCoord::new(1, 0);
+        let visible = field_of_view(HexCoord::ZERO, 3, |coord| coord == blocker);
+
+        assert!(visible.contains(&blocker));
+        assert!(!visible.contains(&HexCoord::new(2, 0)));
+        assert!(!visible.contains(&HexCoord::new(3, 0)));
+        assert!(visible.contains(&HexCoord::new(-3, 0)));
+    }
+}
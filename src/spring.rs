@@ -1,3 +1,5 @@
+use std::f32::consts::{PI, TAU};
+
 use crate::prelude::*;
 use bevy::prelude::*;
 
@@ -7,8 +9,15 @@ impl Plugin for SpringPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
             Update,
-            (apply_translation_springs, apply_scale_springs).run_if(in_state(AppState::InGame)),
-        );
+            (
+                apply_translation_springs,
+                apply_scale_springs,
+                apply_rotation_springs,
+                despawn_shrunk.after(apply_scale_springs),
+            )
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(OnExit(AppState::InGame), cleanup_shrinking);
     }
 }
 
@@ -95,3 +104,93 @@ pub fn apply_scale_springs(mut query: Query<(&mut Transform, &mut ScaleSpring)>)
         }
     }
 }
+
+/// Turns an entity around the vertical axis towards a target angle, always the
+/// shortest way around.
+#[derive(Component, Debug)]
+pub struct RotationSpring {
+    /// The target rotation around the vertical axis, in radians.
+    pub target: f32,
+    pub velocity: f32,
+    pub stiffness: f32,
+    pub damping: f32,
+}
+
+impl RotationSpring {
+    pub fn new(target: f32, stiffness: f32, damping: f32) -> Self {
+        Self {
+            target,
+            velocity: 0.,
+            stiffness,
+            damping,
+        }
+    }
+}
+
+impl Default for RotationSpring {
+    fn default() -> Self {
+        Self::new(0., 0.2, 0.5)
+    }
+}
+
+pub fn apply_rotation_springs(
+    mut query: Query<(&mut Transform, &mut RotationSpring)>,
+    time: Res<Time>,
+) {
+    let time_factor = time.delta_seconds() * 60.0;
+    for (mut transform, mut spring) in query.iter_mut() {
+        let angle = transform.rotation.to_euler(EulerRot::YXZ).0;
+        let offset = (spring.target - angle + PI).rem_euclid(TAU) - PI;
+        let force = offset * spring.stiffness;
+        let damping = spring.velocity * spring.damping;
+        let acceleration = (force - damping) / 1.0;
+        spring.velocity += acceleration * time_factor;
+        let mut angle = angle + spring.velocity * time_factor;
+        if offset.abs() < 0.001 && spring.velocity.abs() < 0.001 {
+            angle = spring.target;
+            spring.velocity = 0.;
+        }
+        transform.rotation = Quat::from_rotation_y(angle);
+    }
+}
+
+/// Shrinks an entity down to nothing with its `ScaleSpring`, and despawns it
+/// once it's gone.
+#[derive(Component, Debug, Default)]
+pub struct DespawnWhenShrunk;
+
+impl DespawnWhenShrunk {
+    /// Start shrinking the entity, if it still exists.
+    pub fn insert(world: &mut World, entity: Entity) {
+        let Some(mut entity) = world.get_entity_mut(entity) else {
+            return;
+        };
+        if let Some(mut scale_spring) = entity.get_mut::<ScaleSpring>() {
+            scale_spring.target = Vec3::ZERO;
+        }
+        entity.insert(DespawnWhenShrunk);
+    }
+}
+
+fn despawn_shrunk(
+    mut commands: Commands,
+    query: Query<(Entity, &Transform, Option<&ScaleSpring>), With<DespawnWhenShrunk>>,
+) {
+    for (entity, transform, scale_spring) in query.iter() {
+        let is_shrunk = match scale_spring {
+            Some(scale_spring) => {
+                scale_spring.target == Vec3::ZERO && transform.scale == Vec3::ZERO
+            }
+            None => true,
+        };
+        if is_shrunk {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn cleanup_shrinking(mut commands: Commands, query: Query<Entity, With<DespawnWhenShrunk>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -28,6 +28,7 @@ pub enum GameState {
     None,
     Playing,
     Building,
+    Demolishing,
 }
 
 pub fn update_state<T: States>(state: T) -> impl Fn(ResMut<NextState<T>>) {
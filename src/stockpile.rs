@@ -0,0 +1,148 @@
+use std::fmt;
+
+use crate::prelude::*;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub struct StockpilePlugin;
+
+impl Plugin for StockpilePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Stockpile>()
+            .add_systems(OnExit(AppState::InGame), reset_stockpile);
+    }
+}
+
+/// A kind of good that can be stockpiled and spent on structures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Good {
+    Wood,
+    Stone,
+    Food,
+    Coins,
+}
+
+impl Good {
+    pub const ALL: [Good; 4] = [Good::Wood, Good::Stone, Good::Food, Good::Coins];
+}
+
+impl fmt::Display for Good {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Good::Wood => "Wood",
+            Good::Stone => "Stone",
+            Good::Food => "Food",
+            Good::Coins => "Coins",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// An amount of each kind of good, such as the cost of a structure. Kinds left
+/// out when written in a file count as none:
+///
+/// ```ron
+/// (wood: 10, stone: 2)
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Goods {
+    pub wood: u32,
+    pub stone: u32,
+    pub food: u32,
+    pub coins: u32,
+}
+
+impl Goods {
+    pub fn get(&self, good: Good) -> u32 {
+        match good {
+            Good::Wood => self.wood,
+            Good::Stone => self.stone,
+            Good::Food => self.food,
+            Good::Coins => self.coins,
+        }
+    }
+
+    fn get_mut(&mut self, good: Good) -> &mut u32 {
+        match good {
+            Good::Wood => &mut self.wood,
+            Good::Stone => &mut self.stone,
+            Good::Food => &mut self.food,
+            Good::Coins => &mut self.coins,
+        }
+    }
+
+    /// Whether there's at least as much of every kind of good here as in
+    /// `other`.
+    pub fn covers(&self, other: &Goods) -> bool {
+        Good::ALL
+            .into_iter()
+            .all(|good| self.get(good) >= other.get(good))
+    }
+
+    /// Half of each amount, rounded down.
+    pub fn halved(&self) -> Goods {
+        let mut halved = *self;
+        for good in Good::ALL {
+            *halved.get_mut(good) /= 2;
+        }
+        halved
+    }
+}
+
+/// The goods the player starts a new game with.
+pub const STARTING_GOODS: Goods = Goods {
+    wood: 50,
+    stone: 20,
+    food: 30,
+    coins: 100,
+};
+
+/// The goods the player has to spend on structures. Saved along with the game.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct Stockpile {
+    goods: Goods,
+}
+
+impl Default for Stockpile {
+    fn default() -> Self {
+        Self::new(STARTING_GOODS)
+    }
+}
+
+impl Stockpile {
+    pub fn new(goods: Goods) -> Self {
+        Self { goods }
+    }
+
+    pub fn goods(&self) -> &Goods {
+        &self.goods
+    }
+
+    pub fn can_afford(&self, cost: &Goods) -> bool {
+        self.goods.covers(cost)
+    }
+
+    /// Take `cost` out of the stockpile. Returns `false` without taking
+    /// anything if there isn't enough of every good.
+    pub fn spend(&mut self, cost: &Goods) -> bool {
+        if !self.can_afford(cost) {
+            return false;
+        }
+        for good in Good::ALL {
+            *self.goods.get_mut(good) -= cost.get(good);
+        }
+        true
+    }
+
+    /// Put `goods` into the stockpile, such as when a structure is refunded.
+    pub fn add(&mut self, goods: &Goods) {
+        for good in Good::ALL {
+            *self.goods.get_mut(good) += goods.get(good);
+        }
+    }
+}
+
+fn reset_stockpile(mut stockpile: ResMut<Stockpile>) {
+    *stockpile = Stockpile::default();
+}
@@ -0,0 +1,139 @@
+use bevy::{ecs::system::SystemState, prelude::*};
+
+use crate::{
+    elevation::TileHeights,
+    history::UndoableCommand,
+    prelude::*,
+    spring::{DespawnWhenShrunk, ScaleSpring, TranslationSpring},
+    stockpile::{Goods, Stockpile},
+};
+
+use super::{
+    planned_structure::PlannedStructure,
+    registry::{StructureDefinition, StructureRegistry},
+    Facing, StructureId,
+};
+
+/// Placing a structure on a tile. Reverting it shrinks the structure away and
+/// refunds its cost, and applying it again pays for a new one that pops up in
+/// its place.
+pub struct PlaceStructure {
+    pub structure: StructureId,
+    pub coord: HexCoord,
+    pub facing: Facing,
+}
+
+impl UndoableCommand for PlaceStructure {
+    fn apply(&mut self, world: &mut World) {
+        if spawn_structure(world, &self.structure, self.coord, self.facing).is_some() {
+            let cost = structure_cost(world, &self.structure);
+            world.resource_mut::<Stockpile>().spend(&cost);
+        }
+    }
+
+    fn revert(&mut self, world: &mut World) {
+        if let Some(entity) = find_structure(world, &self.structure, self.coord) {
+            remove_structure(world, entity);
+            let cost = structure_cost(world, &self.structure);
+            world.resource_mut::<Stockpile>().add(&cost);
+        }
+    }
+
+    fn can_apply(&self, world: &World) -> bool {
+        let cost = structure_cost(world, &self.structure);
+        world.resource::<Stockpile>().can_afford(&cost)
+    }
+}
+
+/// Demolishing a placed structure, which refunds half of its cost. Reverting
+/// it pays the refund back and pops the structure up again.
+pub struct DemolishStructure {
+    pub structure: StructureId,
+    pub coord: HexCoord,
+    pub facing: Facing,
+}
+
+impl UndoableCommand for DemolishStructure {
+    fn apply(&mut self, world: &mut World) {
+        if let Some(entity) = find_structure(world, &self.structure, self.coord) {
+            remove_structure(world, entity);
+            let refund = structure_cost(world, &self.structure).halved();
+            world.resource_mut::<Stockpile>().add(&refund);
+        }
+    }
+
+    fn revert(&mut self, world: &mut World) {
+        if spawn_structure(world, &self.structure, self.coord, self.facing).is_some() {
+            let refund = structure_cost(world, &self.structure).halved();
+            world.resource_mut::<Stockpile>().spend(&refund);
+        }
+    }
+
+    fn can_revert(&self, world: &World) -> bool {
+        let refund = structure_cost(world, &self.structure).halved();
+        world.resource::<Stockpile>().can_afford(&refund)
+    }
+}
+
+/// The cost of the structure with the given id, which is nothing if it isn't
+/// in the registry.
+fn structure_cost(world: &World, id: &StructureId) -> Goods {
+    world
+        .resource::<StructureRegistry>()
+        .get(id)
+        .map(|definition| definition.cost)
+        .unwrap_or_default()
+}
+
+/// Spawns a placed structure on the tile at `coord`, growing from nothing.
+/// Returns `None` if there is no structure with the given id.
+pub fn spawn_structure(
+    world: &mut World,
+    id: &StructureId,
+    coord: HexCoord,
+    facing: Facing,
+) -> Option<Entity> {
+    let mut system_state: SystemState<(TileHeights, Res<HexLayout>, Res<StructureRegistry>)> =
+        SystemState::new(world);
+    let (tile_heights, layout, registry) = system_state.get(world);
+
+    registry.get(id)?;
+    let mut structure = StructureDefinition::spatial_bundle();
+    let tile_height = tile_heights.get(coord).unwrap_or_default();
+    let translation = layout.hex_to_vec3_with_y(coord, tile_height);
+    structure.transform.translation = translation;
+    structure.transform.rotation = facing.rotation();
+
+    Some(
+        world
+            .spawn((
+                structure,
+                id.clone(),
+                coord,
+                facing,
+                TranslationSpring::new(translation, 0.15, 0.5),
+                ScaleSpring::new(Vec3::ONE, 0.15, 0.5),
+            ))
+            .id(),
+    )
+}
+
+/// Shrinks a placed structure away and despawns it. It stops counting as a
+/// structure right away, so it's neither saved nor found again while it
+/// shrinks.
+pub fn remove_structure(world: &mut World, entity: Entity) {
+    DespawnWhenShrunk::insert(world, entity);
+    if let Some(mut entity) = world.get_entity_mut(entity) {
+        entity.remove::<(StructureId, HexCoord)>();
+    }
+}
+
+/// The placed structure with the given id standing on the tile at `coord`, if
+/// any.
+pub fn find_structure(world: &mut World, id: &StructureId, coord: HexCoord) -> Option<Entity> {
+    world
+        .query_filtered::<(Entity, &StructureId, &HexCoord), Without<PlannedStructure>>()
+        .iter(world)
+        .find(|(_, other_id, other_coord)| *other_id == id && **other_coord == coord)
+        .map(|(entity, _, _)| entity)
+}
@@ -0,0 +1,164 @@
+#![allow(clippy::type_complexity)]
+
+use bevy::prelude::*;
+
+use crate::{
+    history::CommandHistory, mouse_position::update_mouse_position, prelude::*,
+    spring::DespawnWhenShrunk, state::GameState, ui::hud::DemolishOnPress,
+};
+
+use super::{
+    commands::DemolishStructure,
+    model::MaterialOverride,
+    occupancy::{index_structures, Occupancy},
+    planned_structure::PlannedStructure,
+    Facing, StructureId,
+};
+
+pub struct DemolitionPlugin;
+
+impl Plugin for DemolitionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnExit(AppState::Loading), initialize_demolition_material)
+            .add_systems(OnExit(GameState::Demolishing), clear_highlights)
+            .add_systems(
+                Update,
+                (
+                    toggle_demolish_mode.run_if(
+                        in_state(GameState::Playing).or_else(in_state(GameState::Demolishing)),
+                    ),
+                    (
+                        highlight_hovered_structure,
+                        demolish_hovered_structure.after(highlight_hovered_structure),
+                    )
+                        .after(update_mouse_position)
+                        .after(index_structures)
+                        .run_if(in_state(GameState::Demolishing)),
+                ),
+            );
+    }
+}
+
+/// The color that structures about to be demolished are tinted in.
+const DEMOLITION_COLOR: Color = Color::rgb(0.9, 0.25, 0.1);
+
+/// The material of structures about to be demolished.
+#[derive(Resource)]
+struct DemolitionMaterial(Handle<StandardMaterial>);
+
+/// Marks a structure that's highlighted for demolition.
+#[derive(Component)]
+struct DemolitionHighlight;
+
+fn initialize_demolition_material(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(DemolitionMaterial(materials.add(StandardMaterial {
+        base_color: DEMOLITION_COLOR,
+        emissive: DEMOLITION_COLOR * 0.3,
+        ..default()
+    })));
+}
+
+/// Switches demolish mode on when its button is pressed, and off again when
+/// it's pressed a second time or Escape is pressed.
+fn toggle_demolish_mode(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<DemolishOnPress>)>,
+    keyboard_input: Res<Input<KeyCode>>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let pressed = interaction_query
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed);
+    let demolishing = *state.get() == GameState::Demolishing;
+
+    if demolishing && (pressed || keyboard_input.just_pressed(KeyCode::Escape)) {
+        next_state.set(GameState::Playing);
+    } else if !demolishing && pressed {
+        next_state.set(GameState::Demolishing);
+    }
+}
+
+/// Highlights the structure covering the tile under the mouse cursor, which
+/// may be any tile of its footprint.
+fn highlight_hovered_structure(
+    mut commands: Commands,
+    structure_query: Query<
+        (Entity, Option<&DemolitionHighlight>),
+        (With<StructureId>, Without<PlannedStructure>),
+    >,
+    demolition_material: Res<DemolitionMaterial>,
+    occupancy: Res<Occupancy>,
+    mouse_position: Res<MousePosition>,
+) {
+    let hovered_structure = occupancy.get(HexCoord::from(&mouse_position.tile));
+
+    for (entity, highlight) in structure_query.iter() {
+        match (hovered_structure == Some(entity), highlight) {
+            (true, None) => {
+                commands.entity(entity).insert((
+                    DemolitionHighlight,
+                    MaterialOverride {
+                        material: demolition_material.0.clone(),
+                        casts_shadows: true,
+                    },
+                ));
+            }
+            (false, Some(_)) => {
+                commands
+                    .entity(entity)
+                    .remove::<(DemolitionHighlight, MaterialOverride)>();
+            }
+            _ => {}
+        }
+    }
+}
+
+fn demolish_hovered_structure(
+    mut commands: Commands,
+    structure_query: Query<
+        (&StructureId, &HexCoord, &Facing),
+        (With<DemolitionHighlight>, Without<PlannedStructure>),
+    >,
+    button_query: Query<&Interaction, With<Button>>,
+    mouse_input: Res<Input<MouseButton>>,
+) {
+    if !mouse_input.just_pressed(MouseButton::Left) {
+        return;
+    }
+    // Clicks on the HUD aren't meant for the structures behind it
+    if button_query
+        .iter()
+        .any(|interaction| *interaction != Interaction::None)
+    {
+        return;
+    }
+
+    for (id, coord, facing) in structure_query.iter() {
+        let command = DemolishStructure {
+            structure: id.clone(),
+            coord: *coord,
+            facing: *facing,
+        };
+        commands.add(move |world: &mut World| {
+            world.resource_scope(|world, mut history: Mut<CommandHistory>| {
+                history.execute(world, command);
+            });
+        });
+    }
+}
+
+/// Gives highlighted structures their own material back, except for those
+/// already on their way out.
+fn clear_highlights(
+    mut commands: Commands,
+    structure_query: Query<Entity, (With<DemolitionHighlight>, Without<DespawnWhenShrunk>)>,
+) {
+    for entity in structure_query.iter() {
+        commands
+            .entity(entity)
+            .remove::<(DemolitionHighlight, MaterialOverride)>();
+    }
+}
@@ -0,0 +1,149 @@
+use bevy::prelude::*;
+
+use crate::{
+    chunks::{tile_corners, HexMeshBuilder},
+    elevation::TileHeights,
+    prelude::*,
+    state::GameState,
+};
+
+use super::{
+    planned_structure::{PlacementCheck, PlannedStructure},
+    registry::StructureRegistry,
+    Facing, StructureId,
+};
+
+pub struct FootprintPlugin;
+
+impl Plugin for FootprintPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FootprintOverlayAssets>()
+            .add_systems(
+                OnExit(AppState::Loading),
+                initialize_footprint_overlay_assets,
+            )
+            .add_systems(OnExit(GameState::Building), despawn_footprint_overlays)
+            .add_systems(
+                Update,
+                (spawn_footprint_overlays, update_footprint_overlays)
+                    .chain()
+                    .run_if(in_state(GameState::Building)),
+            );
+    }
+}
+
+/// How far above the top face of a tile its footprint overlay is drawn, to
+/// avoid z-fighting with the tile and the hover overlay.
+const FOOTPRINT_OVERLAY_OFFSET: f32 = 0.004;
+
+/// Highlights one tile of the footprint of a planned structure, in green when
+/// the structure can be placed and in red when it can't.
+#[derive(Component)]
+struct FootprintOverlay {
+    structure: Entity,
+    /// The offset of the tile in the footprint, before it's rotated with the
+    /// structure.
+    offset: HexCoord,
+}
+
+#[derive(Resource, Default)]
+struct FootprintOverlayAssets {
+    mesh: Handle<Mesh>,
+    valid: Handle<StandardMaterial>,
+    invalid: Handle<StandardMaterial>,
+}
+
+fn initialize_footprint_overlay_assets(
+    mut assets: ResMut<FootprintOverlayAssets>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    layout: Res<HexLayout>,
+) {
+    let mut builder = HexMeshBuilder::default();
+    builder.add_prism(Vec2::ZERO, &tile_corners(&layout), 0., Color::WHITE);
+    assets.mesh = meshes.add(builder.build());
+
+    let mut overlay_material = |color: Color| {
+        materials.add(StandardMaterial {
+            base_color: color.with_a(0.4),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        })
+    };
+    assets.valid = overlay_material(Color::rgb(0.3, 0.9, 0.4));
+    assets.invalid = overlay_material(Color::rgb(0.95, 0.25, 0.2));
+}
+
+fn spawn_footprint_overlays(
+    mut commands: Commands,
+    structure_query: Query<(Entity, &StructureId), Added<PlannedStructure>>,
+    registry: Res<StructureRegistry>,
+    assets: Res<FootprintOverlayAssets>,
+) {
+    for (structure, id) in structure_query.iter() {
+        let Some(definition) = registry.get(id) else {
+            continue;
+        };
+        for offset in &definition.footprint {
+            commands.spawn((
+                PbrBundle {
+                    mesh: assets.mesh.clone(),
+                    material: assets.valid.clone(),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                FootprintOverlay {
+                    structure,
+                    offset: *offset,
+                },
+            ));
+        }
+    }
+}
+
+/// Moves footprint overlays onto the tiles their planned structure would
+/// cover, and despawns them once the structure is placed or cancelled.
+fn update_footprint_overlays(
+    mut commands: Commands,
+    mut overlay_query: Query<(
+        Entity,
+        &FootprintOverlay,
+        &mut Transform,
+        &mut Visibility,
+        &mut Handle<StandardMaterial>,
+    )>,
+    structure_query: Query<(&HexCoord, &Facing, &PlacementCheck), With<PlannedStructure>>,
+    assets: Res<FootprintOverlayAssets>,
+    tile_heights: TileHeights,
+    layout: Res<HexLayout>,
+) {
+    for (entity, overlay, mut transform, mut visibility, mut material) in overlay_query.iter_mut() {
+        let Ok((coord, facing, placement_check)) = structure_query.get(overlay.structure) else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+
+        let tile = *coord + overlay.offset.rotate_around(HexCoord::ZERO, facing.steps());
+        let Some(tile_height) = tile_heights.get(tile) else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        *visibility = Visibility::Visible;
+        transform.translation =
+            layout.hex_to_vec3_with_y(tile, tile_height + FOOTPRINT_OVERLAY_OFFSET);
+        *material = match placement_check.0 {
+            Ok(()) => assets.valid.clone(),
+            Err(_) => assets.invalid.clone(),
+        };
+    }
+}
+
+fn despawn_footprint_overlays(
+    mut commands: Commands,
+    overlay_query: Query<Entity, With<FootprintOverlay>>,
+) {
+    for overlay_entity in overlay_query.iter() {
+        commands.entity(overlay_entity).despawn_recursive();
+    }
+}
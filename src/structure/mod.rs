@@ -1,42 +1,203 @@
-pub mod house;
-mod planned_structure;
+pub mod commands;
+pub mod demolition;
+pub mod footprint;
+pub mod model;
+pub mod occupancy;
+pub mod placement;
+pub mod planned_structure;
+pub mod registry;
+
+use std::{collections::HashMap, f32::consts::PI, fmt};
 
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    map_file::MapFile,
+    map_shape::MapAssets,
+    prelude::*,
+    save::LoadedSave,
+    spring::{ScaleSpring, TranslationSpring},
+};
 
-use crate::state::AppState;
+use self::{
+    planned_structure::PlannedStructure,
+    registry::{build_structure_registry, StructureDefinition, StructureRegistry},
+};
 
 pub struct StructurePlugin;
 
 impl Plugin for StructurePlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<AssetsByStructureType>()
-            .add_plugins(house::HousePlugin)
+        app.add_plugins(registry::StructureRegistryPlugin)
             .add_plugins(planned_structure::PlannedStructurePlugin)
+            .add_plugins(demolition::DemolitionPlugin)
+            .add_plugins(footprint::FootprintPlugin)
+            .add_plugins(model::StructureModelPlugin)
+            .add_plugins(occupancy::OccupancyPlugin)
+            .add_plugins(placement::PlacementPlugin)
+            .add_systems(
+                OnExit(AppState::Loading),
+                spawn_initial_structures.after(build_structure_registry),
+            )
             .add_systems(OnExit(AppState::InGame), cleanup);
     }
 }
 
-#[derive(Clone)]
-pub enum StructureType {
-    House,
+/// The id of a structure definition in the `StructureRegistry`, such as
+/// `"house"`. Placed structures carry the id of their definition.
+#[derive(Component, Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct StructureId(pub String);
+
+impl StructureId {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self(id.into())
+    }
+}
+
+impl fmt::Display for StructureId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Which of the six hex directions a structure faces, as a number of 60° steps
+/// from its default direction. Each step turns the structure counter-clockwise
+/// when seen from above, the same way as `Quat::from_rotation_y` with a
+/// positive angle, and its footprint the same way as `HexCoord::rotate_around`.
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Facing(u8);
+
+impl Facing {
+    pub fn new(steps: i32) -> Self {
+        Self(steps.rem_euclid(6) as u8)
+    }
+
+    pub fn steps(self) -> i32 {
+        self.0 as i32
+    }
+
+    /// This facing turned by the given number of 60° steps.
+    pub fn rotated(self, steps: i32) -> Self {
+        Self::new(self.steps() + steps)
+    }
+
+    /// The rotation around the vertical axis, in radians.
+    pub fn angle(self) -> f32 {
+        self.steps() as f32 * PI / 3.
+    }
+
+    pub fn rotation(self) -> Quat {
+        Quat::from_rotation_y(self.angle())
+    }
 }
 
-pub trait Structure {
-    fn assets(&self, world: &mut World) -> Option<StructureAssets>;
-    fn transform(&self) -> Transform;
+/// The assets a structure's model is spawned from, built from its definition
+/// when the registry is built.
+#[derive(Debug, Clone)]
+pub enum StructureAssets {
+    Mesh {
+        mesh: Handle<Mesh>,
+        material: Handle<StandardMaterial>,
+    },
+    Scene(Handle<Scene>),
 }
 
-#[derive(Default, Clone)]
-pub struct StructureAssets {
-    mesh: Handle<Mesh>,
-    material: Handle<StandardMaterial>,
+impl Default for StructureAssets {
+    fn default() -> Self {
+        StructureAssets::Mesh {
+            mesh: default(),
+            material: default(),
+        }
+    }
 }
 
-#[derive(Resource, Default, Clone)]
-pub struct AssetsByStructureType {
-    pub house: StructureAssets,
+fn cleanup(
+    mut commands: Commands,
+    mut registry: ResMut<StructureRegistry>,
+    structure_query: Query<Entity, (With<StructureId>, Without<PlannedStructure>)>,
+) {
+    for structure_entity in structure_query.iter() {
+        commands.entity(structure_entity).despawn_recursive();
+    }
+
+    *registry = Default::default();
 }
 
-fn cleanup(mut assets: ResMut<AssetsByStructureType>) {
-    *assets = Default::default();
+/// Spawns the structures of a loaded save, or else those placed in a
+/// hand-authored map file.
+fn spawn_initial_structures(
+    mut commands: Commands,
+    registry: Res<StructureRegistry>,
+    map_assets: Res<MapAssets>,
+    map_files: Res<Assets<MapFile>>,
+    loaded_save: Res<LoadedSave>,
+    layout: Res<HexLayout>,
+) {
+    let map_file = map_assets
+        .file
+        .as_ref()
+        .and_then(|file| map_files.get(file));
+    let (tiles, structures): (_, Vec<_>) = match (&loaded_save.0, map_file) {
+        (Some(save), _) => (
+            &save.tiles,
+            save.structures
+                .iter()
+                .map(|saved| (&saved.structure, saved.coord, saved.facing, true))
+                .collect(),
+        ),
+        (None, Some(map_file)) => (
+            &map_file.tiles,
+            map_file
+                .structures
+                .iter()
+                .map(|placed| (&placed.structure, placed.coord, placed.facing, false))
+                .collect(),
+        ),
+        (None, None) => return,
+    };
+
+    // The tiles are spawned in the same frame, so their heights come from the
+    // save or map file rather than from the tile entities
+    let tile_heights: HashMap<HexCoord, f32> = tiles
+        .iter()
+        .map(|tile| (tile.coord, tile.elevation.height()))
+        .collect();
+
+    for (id, coord, facing, restored) in structures {
+        let Some(definition) = registry.get(id) else {
+            warn!("Skipping unknown structure {id:?} at {coord:?}");
+            continue;
+        };
+        // Map files are checked before the registry is built, so only the tile
+        // a structure stands on is known to exist
+        if let Some(missing) = definition
+            .tiles(coord, facing)
+            .find(|tile| !tile_heights.contains_key(tile))
+        {
+            warn!("Skipping structure {id:?} at {coord:?}, which covers {missing:?} where there is no tile");
+            continue;
+        }
+        let mut structure = StructureDefinition::spatial_bundle();
+        let tile_height = tile_heights.get(&coord).copied().unwrap_or_default();
+        let translation = layout.hex_to_vec3_with_y(coord, tile_height);
+        structure.transform.translation = translation;
+        structure.transform.rotation = facing.rotation();
+        // Saved structures are restored exactly as they were, while those
+        // from map files pop up like newly built ones
+        if restored {
+            structure.transform.scale = Vec3::ONE;
+        }
+
+        commands.spawn((
+            structure,
+            id.clone(),
+            coord,
+            facing,
+            TranslationSpring::new(translation, 0.15, 0.5),
+            ScaleSpring::new(Vec3::ONE, 0.15, 0.5),
+        ));
+    }
 }
@@ -0,0 +1,118 @@
+use bevy::{pbr::NotShadowCaster, prelude::*};
+
+use crate::prelude::*;
+
+use super::{registry::StructureRegistry, StructureAssets, StructureId};
+
+pub struct StructureModelPlugin;
+
+impl Plugin for StructureModelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                spawn_structure_models,
+                restore_own_materials,
+                apply_material_overrides
+                    .after(spawn_structure_models)
+                    .after(restore_own_materials),
+            )
+                .run_if(in_state(AppState::InGame)),
+        );
+    }
+}
+
+/// Shows every mesh in the model of a structure with the given material instead
+/// of its own, such as to show planned structures as ghosts. Removing it gives
+/// the meshes their own materials back.
+#[derive(Component, Clone)]
+pub struct MaterialOverride {
+    pub material: Handle<StandardMaterial>,
+    pub casts_shadows: bool,
+}
+
+/// The material a mesh gets back once the `MaterialOverride` of its structure
+/// is removed.
+#[derive(Component)]
+struct OwnMaterial(Handle<StandardMaterial>);
+
+/// Gives new structures their model as a child, which carries the rotation,
+/// scale and offset of the model on its own so that the structure itself is
+/// free to turn and grow.
+fn spawn_structure_models(
+    mut commands: Commands,
+    structure_query: Query<(Entity, &StructureId), Added<StructureId>>,
+    registry: Res<StructureRegistry>,
+) {
+    for (entity, id) in structure_query.iter() {
+        let Some(definition) = registry.get(id) else {
+            continue;
+        };
+        let transform = definition.model_transform();
+        commands
+            .entity(entity)
+            .with_children(|commands| match &definition.assets {
+                StructureAssets::Mesh { mesh, material } => {
+                    commands.spawn(PbrBundle {
+                        mesh: mesh.clone(),
+                        material: material.clone(),
+                        transform,
+                        ..default()
+                    });
+                }
+                StructureAssets::Scene(scene) => {
+                    commands.spawn(SceneBundle {
+                        scene: scene.clone(),
+                        transform,
+                        ..default()
+                    });
+                }
+            });
+    }
+}
+
+fn apply_material_overrides(
+    mut commands: Commands,
+    structure_query: Query<(Entity, &MaterialOverride)>,
+    children_query: Query<&Children>,
+    mut mesh_query: Query<(&mut Handle<StandardMaterial>, Option<&OwnMaterial>)>,
+) {
+    // Scenes spawn their meshes a few frames after their structure, so this
+    // keeps looking for new ones for as long as the override lasts
+    for (entity, material_override) in structure_query.iter() {
+        for descendant in children_query.iter_descendants(entity) {
+            let Ok((mut material, own_material)) = mesh_query.get_mut(descendant) else {
+                continue;
+            };
+            if *material == material_override.material {
+                continue;
+            }
+            let material = std::mem::replace(&mut *material, material_override.material.clone());
+            if own_material.is_none() {
+                let mut descendant = commands.entity(descendant);
+                descendant.insert(OwnMaterial(material));
+                if !material_override.casts_shadows {
+                    descendant.insert(NotShadowCaster);
+                }
+            }
+        }
+    }
+}
+
+fn restore_own_materials(
+    mut commands: Commands,
+    mut removed_overrides: RemovedComponents<MaterialOverride>,
+    children_query: Query<&Children>,
+    mut mesh_query: Query<(&mut Handle<StandardMaterial>, &OwnMaterial)>,
+) {
+    for entity in removed_overrides.read() {
+        for descendant in children_query.iter_descendants(entity) {
+            if let Ok((mut material, own_material)) = mesh_query.get_mut(descendant) {
+                *material = own_material.0.clone();
+                commands
+                    .entity(descendant)
+                    .remove::<(OwnMaterial, NotShadowCaster)>();
+            }
+        }
+    }
+}
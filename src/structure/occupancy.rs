@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::prelude::*;
+
+use super::{
+    planned_structure::PlannedStructure, registry::StructureRegistry, Facing, StructureId,
+};
+
+pub struct OccupancyPlugin;
+
+impl Plugin for OccupancyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Occupancy>()
+            .add_systems(Update, index_structures.run_if(in_state(AppState::InGame)))
+            .add_systems(OnExit(AppState::InGame), clear_occupancy);
+    }
+}
+
+/// Which placed structure covers each tile, counting every tile of its
+/// footprint. Kept in sync with placed and removed structures by
+/// `index_structures`.
+#[derive(Resource, Default)]
+pub struct Occupancy {
+    occupants: HashMap<HexCoord, Entity>,
+    coords: HashMap<Entity, Vec<HexCoord>>,
+}
+
+impl Occupancy {
+    /// The structure covering the tile at the given coordinate, if any.
+    pub fn get(&self, coord: HexCoord) -> Option<Entity> {
+        self.occupants.get(&coord).copied()
+    }
+
+    /// Whether a structure covers the tile at the given coordinate.
+    pub fn is_occupied(&self, coord: HexCoord) -> bool {
+        self.occupants.contains_key(&coord)
+    }
+
+    /// Every covered tile, along with the structure covering it.
+    pub fn iter(&self) -> impl Iterator<Item = (HexCoord, Entity)> + '_ {
+        self.occupants
+            .iter()
+            .map(|(coord, entity)| (*coord, *entity))
+    }
+
+    fn insert(&mut self, coords: Vec<HexCoord>, entity: Entity) {
+        self.remove(entity);
+        for coord in coords.iter() {
+            if let Some(previous) = self.occupants.insert(*coord, entity) {
+                self.remove(previous);
+            }
+        }
+        self.coords.insert(entity, coords);
+    }
+
+    fn remove(&mut self, entity: Entity) {
+        let Some(coords) = self.coords.remove(&entity) else {
+            return;
+        };
+        for coord in coords {
+            if self.get(coord) == Some(entity) {
+                self.occupants.remove(&coord);
+            }
+        }
+    }
+}
+
+/// Structures occupy their tiles once they're placed, which is when they're
+/// spawned, or when a planned structure is built. They free them again as soon
+/// as they stop being structures, even if they're still shrinking away.
+pub fn index_structures(
+    placed_query: Query<(&StructureId, &HexCoord, &Facing), Without<PlannedStructure>>,
+    added_query: Query<Entity, Added<StructureId>>,
+    mut built_structures: RemovedComponents<PlannedStructure>,
+    mut removed_structures: RemovedComponents<StructureId>,
+    mut occupancy: ResMut<Occupancy>,
+    registry: Res<StructureRegistry>,
+) {
+    for entity in removed_structures.read() {
+        occupancy.remove(entity);
+    }
+
+    for entity in added_query.iter().chain(built_structures.read()) {
+        let Ok((id, coord, facing)) = placed_query.get(entity) else {
+            continue;
+        };
+        if let Some(definition) = registry.get(id) {
+            occupancy.insert(definition.tiles(*coord, *facing).collect(), entity);
+        }
+    }
+}
+
+fn clear_occupancy(mut occupancy: ResMut<Occupancy>) {
+    *occupancy = Occupancy::default();
+}
@@ -0,0 +1,409 @@
+use std::{collections::HashMap, fmt};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::Deserialize;
+
+use crate::{
+    prelude::*,
+    terrain::Terrain,
+    tiles::{Tile, TileIndex},
+};
+
+use super::{
+    occupancy::Occupancy,
+    registry::{build_structure_registry, StructureRegistry},
+    StructureId,
+};
+
+pub struct PlacementPlugin;
+
+impl Plugin for PlacementPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlacementRules>().add_systems(
+            OnExit(AppState::Loading),
+            configure_placement_rules.after(build_structure_registry),
+        );
+    }
+}
+
+/// A condition that the tiles under a structure must meet for it to be placed
+/// on them. `tiles` are all the tiles of the structure's footprint, which are
+/// known to exist by the time rules are checked.
+pub trait PlacementRule: Send + Sync {
+    fn check(&self, tiles: &[HexCoord], context: &PlacementContext) -> Result<(), PlacementError>;
+}
+
+/// Why a structure can't be placed where it is.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlacementError {
+    NoTile,
+    Occupied,
+    UnbuildableTerrain(Terrain),
+    /// Not next to a structure of the given type, named by its display name.
+    NotAdjacentTo(String),
+    /// Too far from a structure of the given type, named by its display name.
+    TooFarFrom {
+        structure: String,
+        range: u32,
+    },
+}
+
+impl fmt::Display for PlacementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlacementError::NoTile => write!(f, "There is no room here"),
+            PlacementError::Occupied => write!(f, "Something is already built here"),
+            PlacementError::UnbuildableTerrain(terrain) => {
+                write!(f, "Can't build on {}", terrain.properties().name)
+            }
+            PlacementError::NotAdjacentTo(structure) => {
+                write!(f, "Must be next to a {structure}")
+            }
+            PlacementError::TooFarFrom { structure, range } => {
+                write!(f, "Must be within {range} tiles of a {structure}")
+            }
+        }
+    }
+}
+
+/// The state of the board that placement rules are checked against.
+#[derive(SystemParam)]
+pub struct PlacementContext<'w, 's> {
+    occupancy: Res<'w, Occupancy>,
+    tile_index: Res<'w, TileIndex>,
+    terrain_query: Query<'w, 's, &'static Terrain, With<Tile>>,
+    structure_query: Query<'w, 's, &'static StructureId>,
+    registry: Res<'w, StructureRegistry>,
+}
+
+impl<'w, 's> PlacementContext<'w, 's> {
+    /// The terrain of the tile at the given coordinate, or `None` if there is
+    /// no tile there.
+    pub fn terrain(&self, coord: HexCoord) -> Option<Terrain> {
+        let entity = self.tile_index.get(coord)?;
+        self.terrain_query.get(entity).ok().copied()
+    }
+
+    pub fn is_occupied(&self, coord: HexCoord) -> bool {
+        self.occupancy.is_occupied(coord)
+    }
+
+    /// The type of the structure standing on the tile at the given coordinate,
+    /// if any.
+    pub fn structure(&self, coord: HexCoord) -> Option<&StructureId> {
+        let entity = self.occupancy.get(coord)?;
+        self.structure_query.get(entity).ok()
+    }
+
+    /// The name shown to players for the structure with the given id, or the
+    /// id itself if it isn't in the registry.
+    pub fn structure_name(&self, id: &StructureId) -> String {
+        self.registry
+            .get(id)
+            .map_or_else(|| id.to_string(), |definition| definition.name.clone())
+    }
+}
+
+/// The tiles must not already have a structure on them.
+pub struct TileIsFree;
+
+impl PlacementRule for TileIsFree {
+    fn check(&self, tiles: &[HexCoord], context: &PlacementContext) -> Result<(), PlacementError> {
+        if tiles.iter().any(|coord| context.is_occupied(*coord)) {
+            return Err(PlacementError::Occupied);
+        }
+        Ok(())
+    }
+}
+
+/// The terrain of every tile must allow building on it.
+pub struct BuildableTerrain;
+
+impl PlacementRule for BuildableTerrain {
+    fn check(&self, tiles: &[HexCoord], context: &PlacementContext) -> Result<(), PlacementError> {
+        for terrain in tiles.iter().filter_map(|coord| context.terrain(*coord)) {
+            if !terrain.is_buildable() {
+                return Err(PlacementError::UnbuildableTerrain(terrain));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One of the tiles next to the structure must have a structure of the given
+/// type on it, such as a road.
+pub struct AdjacentTo(pub StructureId);
+
+impl PlacementRule for AdjacentTo {
+    fn check(&self, tiles: &[HexCoord], context: &PlacementContext) -> Result<(), PlacementError> {
+        if tiles
+            .iter()
+            .flat_map(|coord| coord.neighbors())
+            .filter(|neighbor| !tiles.contains(neighbor))
+            .any(|neighbor| context.structure(neighbor) == Some(&self.0))
+        {
+            return Ok(());
+        }
+        Err(PlacementError::NotAdjacentTo(
+            context.structure_name(&self.0),
+        ))
+    }
+}
+
+/// A structure of the given type, such as a town center, must stand within
+/// `range` steps of one of the tiles.
+pub struct WithinRangeOf {
+    pub structure: StructureId,
+    pub range: u32,
+}
+
+impl PlacementRule for WithinRangeOf {
+    fn check(&self, tiles: &[HexCoord], context: &PlacementContext) -> Result<(), PlacementError> {
+        if tiles
+            .iter()
+            .flat_map(|coord| coord.range(self.range))
+            .any(|nearby| context.structure(nearby) == Some(&self.structure))
+        {
+            return Ok(());
+        }
+        Err(PlacementError::TooFarFrom {
+            structure: context.structure_name(&self.structure),
+            range: self.range,
+        })
+    }
+}
+
+/// A placement rule as written in a structure definition.
+#[derive(Debug, Clone, Deserialize)]
+pub enum PlacementRuleDefinition {
+    TileIsFree,
+    BuildableTerrain,
+    AdjacentTo(StructureId),
+    WithinRangeOf { structure: StructureId, range: u32 },
+}
+
+impl PlacementRuleDefinition {
+    fn to_rule(&self) -> Box<dyn PlacementRule> {
+        match self {
+            PlacementRuleDefinition::TileIsFree => Box::new(TileIsFree),
+            PlacementRuleDefinition::BuildableTerrain => Box::new(BuildableTerrain),
+            PlacementRuleDefinition::AdjacentTo(structure) => {
+                Box::new(AdjacentTo(structure.clone()))
+            }
+            PlacementRuleDefinition::WithinRangeOf { structure, range } => {
+                Box::new(WithinRangeOf {
+                    structure: structure.clone(),
+                    range: *range,
+                })
+            }
+        }
+    }
+}
+
+/// The rules each kind of structure must follow to be placed, as listed in
+/// their definitions. Plugins can add more with `PlacementRules::add` after
+/// `configure_placement_rules`.
+#[derive(Resource, Default)]
+pub struct PlacementRules {
+    rules: HashMap<StructureId, Vec<Box<dyn PlacementRule>>>,
+}
+
+impl PlacementRules {
+    /// Add a rule that structures of the given kind must follow.
+    pub fn add(&mut self, structure: StructureId, rule: Box<dyn PlacementRule>) -> &mut Self {
+        self.rules.entry(structure).or_default().push(rule);
+        self
+    }
+
+    /// Checks whether a structure can be placed on `tiles`, all the tiles of
+    /// its footprint, returning the first rule it breaks. Every structure
+    /// needs tiles to stand on, so that is checked before any of the rules.
+    pub fn check(
+        &self,
+        structure: &StructureId,
+        tiles: &[HexCoord],
+        context: &PlacementContext,
+    ) -> Result<(), PlacementError> {
+        if tiles.iter().any(|coord| context.terrain(*coord).is_none()) {
+            return Err(PlacementError::NoTile);
+        }
+        self.rules
+            .get(structure)
+            .into_iter()
+            .flatten()
+            .try_for_each(|rule| rule.check(tiles, context))
+    }
+}
+
+pub fn configure_placement_rules(
+    mut rules: ResMut<PlacementRules>,
+    registry: Res<StructureRegistry>,
+) {
+    *rules = PlacementRules::default();
+    for definition in registry.iter() {
+        for rule in definition.placement.iter() {
+            rules.add(definition.id.clone(), rule.to_rule());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::{RunSystemOnce, SystemState};
+
+    use crate::{
+        structure::{occupancy::index_structures, Facing},
+        tiles::index_tiles,
+    };
+
+    use super::*;
+
+    const DEFINITIONS: [&str; 5] = [
+        r#"(id: "house", name: "House", model: Box(size: (0.4, 0.4, 0.4), color: (1, 1, 1)),
+            placement: [TileIsFree, BuildableTerrain])"#,
+        r#"(id: "road", name: "Road", model: Box(size: (0.4, 0.1, 0.4), color: (1, 1, 1)))"#,
+        r#"(id: "market", name: "Market", model: Box(size: (0.8, 0.4, 0.4), color: (1, 1, 1)),
+            footprint: [(q: 0, r: 0), (q: 1, r: 0)], placement: [TileIsFree, AdjacentTo("road")])"#,
+        r#"(id: "town_center", name: "Town Center", model: Box(size: (0.4, 0.8, 0.4), color: (1, 1, 1)))"#,
+        r#"(id: "well", name: "Well", model: Box(size: (0.2, 0.2, 0.2), color: (1, 1, 1)),
+            placement: [WithinRangeOf(structure: "town_center", range: 2)])"#,
+    ];
+
+    /// A hexagon of grass tiles around the origin with the structures in
+    /// `DEFINITIONS` registered.
+    struct Board {
+        world: World,
+    }
+
+    impl Board {
+        fn new() -> Self {
+            let mut world = World::new();
+            world.init_resource::<TileIndex>();
+            world.init_resource::<Occupancy>();
+            world.init_resource::<PlacementRules>();
+            let mut registry = StructureRegistry::default();
+            for text in DEFINITIONS {
+                registry.insert(ron::from_str(text).unwrap());
+            }
+            world.insert_resource(registry);
+            world.run_system_once(configure_placement_rules);
+
+            for coord in HexCoord::ZERO.range(4) {
+                world.spawn((coord, Terrain::Grass, Tile));
+            }
+            Self { world }
+        }
+
+        fn set_terrain(&mut self, coord: HexCoord, terrain: Terrain) -> &mut Self {
+            let mut tile_query = self.world.query::<(&HexCoord, &mut Terrain)>();
+            for (tile, mut tile_terrain) in tile_query.iter_mut(&mut self.world) {
+                if *tile == coord {
+                    *tile_terrain = terrain;
+                }
+            }
+            self
+        }
+
+        fn place(&mut self, id: &str, coord: HexCoord) -> &mut Self {
+            self.world
+                .spawn((StructureId::new(id), coord, Facing::default()));
+            self
+        }
+
+        fn check(&mut self, id: &str, coord: HexCoord) -> Result<(), PlacementError> {
+            self.world.run_system_once(index_tiles);
+            self.world.run_system_once(index_structures);
+
+            let mut system_state: SystemState<(
+                Res<PlacementRules>,
+                Res<StructureRegistry>,
+                PlacementContext,
+            )> = SystemState::new(&mut self.world);
+            let (rules, registry, context) = system_state.get(&self.world);
+            let id = StructureId::new(id);
+            let tiles: Vec<_> = registry
+                .get(&id)
+                .unwrap()
+                .tiles(coord, Facing::default())
+                .collect();
+            rules.check(&id, &tiles, &context)
+        }
+    }
+
+    #[test]
+    fn structures_need_tiles_under_their_whole_footprint() {
+        let mut board = Board::new();
+
+        assert_eq!(
+            board.check("road", HexCoord::new(5, 0)),
+            Err(PlacementError::NoTile)
+        );
+        assert_eq!(
+            board.check("house", HexCoord::new(5, 0)),
+            Err(PlacementError::NoTile)
+        );
+        board.place("road", HexCoord::new(3, -1));
+        assert_eq!(
+            board.check("market", HexCoord::new(4, -1)),
+            Err(PlacementError::NoTile)
+        );
+    }
+
+    #[test]
+    fn tiles_must_be_free() {
+        let mut board = Board::new();
+        board
+            .place("house", HexCoord::ZERO)
+            .place("road", HexCoord::new(0, -1));
+
+        assert_eq!(
+            board.check("house", HexCoord::ZERO),
+            Err(PlacementError::Occupied)
+        );
+        assert_eq!(board.check("house", HexCoord::new(0, 1)), Ok(()));
+        assert_eq!(
+            board.check("market", HexCoord::new(-1, 0)),
+            Err(PlacementError::Occupied)
+        );
+    }
+
+    #[test]
+    fn terrain_must_be_buildable() {
+        let mut board = Board::new();
+        board.set_terrain(HexCoord::ZERO, Terrain::Water);
+
+        assert_eq!(
+            board.check("house", HexCoord::ZERO),
+            Err(PlacementError::UnbuildableTerrain(Terrain::Water))
+        );
+        assert_eq!(board.check("house", HexCoord::new(1, 0)), Ok(()));
+    }
+
+    #[test]
+    fn adjacent_structures_must_be_next_to_the_footprint() {
+        let mut board = Board::new();
+        board.place("road", HexCoord::new(2, 0));
+
+        // The market covers (0, 0) and (1, 0), which is next to the road
+        assert_eq!(board.check("market", HexCoord::ZERO), Ok(()));
+        assert_eq!(
+            board.check("market", HexCoord::new(-2, 0)),
+            Err(PlacementError::NotAdjacentTo("Road".to_string()))
+        );
+    }
+
+    #[test]
+    fn structures_in_range_must_be_close_enough() {
+        let mut board = Board::new();
+        board.place("town_center", HexCoord::ZERO);
+
+        assert_eq!(board.check("well", HexCoord::new(2, -2)), Ok(()));
+        assert_eq!(
+            board.check("well", HexCoord::new(3, -2)),
+            Err(PlacementError::TooFarFrom {
+                structure: "Town Center".to_string(),
+                range: 2,
+            })
+        );
+    }
+}
@@ -1,22 +1,34 @@
+#![allow(clippy::type_complexity)]
+
 use crate::{
+    elevation::TileHeights,
+    history::CommandHistory,
     mouse_position::update_mouse_position,
     prelude::*,
-    spring::{ScaleSpring, TranslationSpring},
+    spring::{DespawnWhenShrunk, RotationSpring, ScaleSpring, TranslationSpring},
     state::GameState,
-    structure::house::HouseBundle,
-    tile_position::set_tile_position,
-    tiles::Tile,
+    stockpile::{Goods, Stockpile},
+    structure::occupancy::index_structures,
+    tile_transform::set_tile_position,
     ui::hud::BuildOnPress,
 };
-use bevy::prelude::*;
+use bevy::{input::mouse::MouseWheel, prelude::*};
 
-use super::{AssetsByStructureType, StructureType};
+use super::{
+    commands::PlaceStructure,
+    model::MaterialOverride,
+    placement::{PlacementContext, PlacementError, PlacementRules},
+    registry::{StructureDefinition, StructureRegistry},
+    Facing, StructureId,
+};
 
 pub struct PlannedStructurePlugin;
 
 impl Plugin for PlannedStructurePlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnExit(AppState::InGame), cleanup)
+        app.init_resource::<GhostMaterials>()
+            .add_systems(OnExit(AppState::Loading), initialize_ghost_materials)
+            .add_systems(OnExit(AppState::InGame), cleanup)
             .add_systems(
                 Update,
                 (
@@ -26,6 +38,15 @@ impl Plugin for PlannedStructurePlugin {
                         follow_mouse
                             .after(update_mouse_position)
                             .before(set_tile_position),
+                        rotate_planned_structure.before(check_placement),
+                        check_placement
+                            .after(follow_mouse)
+                            .after(index_structures)
+                            .before(build_structure),
+                        tint_planned_structure
+                            .after(check_placement)
+                            .before(build_structure),
+                        cancel_building.after(follow_mouse).before(build_structure),
                     )
                         .run_if(in_state(GameState::Building)),
                 ),
@@ -33,51 +54,130 @@ impl Plugin for PlannedStructurePlugin {
     }
 }
 
+/// Marks a structure that follows the mouse cursor until it's placed.
 #[derive(Component)]
-struct PlannedStructure;
+pub struct PlannedStructure;
+
+/// Whether a planned structure can be placed on the tile it's hovering over,
+/// and if not, why.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct PlacementCheck(pub Result<(), PlacementError>);
+
+/// The translucent materials of planned structures, tinted by whether they can
+/// be placed where they are.
+#[derive(Resource, Default)]
+struct GhostMaterials {
+    valid: Handle<StandardMaterial>,
+    invalid: Handle<StandardMaterial>,
+}
+
+/// The goods taken from the stockpile for a planned structure, which are
+/// refunded if it's cancelled.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct ReservedGoods(pub Goods);
 
 #[derive(Bundle)]
 pub struct PlannedStructureBundle {
-    tile_position: TilePosition,
+    hex_coord: HexCoord,
+    facing: Facing,
     translation_spring: TranslationSpring,
     scale_spring: ScaleSpring,
+    rotation_spring: RotationSpring,
     planned_structure: PlannedStructure,
+    placement_check: PlacementCheck,
+    reserved_goods: ReservedGoods,
 }
 
+/// How far above its tile a planned structure hovers until it's placed.
+const PLANNED_STRUCTURE_HOVER_HEIGHT: f32 = 0.1;
+
 impl PlannedStructureBundle {
-    fn from_tile_position(tile_position: TilePosition) -> Self {
-        let translation = tile_position.to_vec3_with_y(0.1);
+    fn from_hex_coord(
+        hex_coord: HexCoord,
+        cost: Goods,
+        rotation: Quat,
+        layout: &HexLayout,
+        tile_height: f32,
+    ) -> Self {
+        let translation =
+            layout.hex_to_vec3_with_y(hex_coord, tile_height + PLANNED_STRUCTURE_HOVER_HEIGHT);
         Self {
-            tile_position,
+            hex_coord,
+            facing: Facing::default(),
             translation_spring: TranslationSpring::new(translation, 0.15, 0.5),
             scale_spring: ScaleSpring::new(Vec3::ONE, 0.15, 0.5),
+            rotation_spring: RotationSpring::new(rotation.to_euler(EulerRot::YXZ).0, 0.2, 0.5),
             planned_structure: PlannedStructure,
+            placement_check: PlacementCheck(Ok(())),
+            reserved_goods: ReservedGoods(cost),
         }
     }
 }
 
+fn initialize_ghost_materials(
+    mut ghost_materials: ResMut<GhostMaterials>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let mut ghost_material = |color: Color| {
+        materials.add(StandardMaterial {
+            base_color: color.with_a(0.5),
+            emissive: color * 0.4,
+            alpha_mode: AlphaMode::Blend,
+            ..default()
+        })
+    };
+    ghost_materials.valid = ghost_material(Color::rgb(0.3, 0.9, 0.4));
+    ghost_materials.invalid = ghost_material(Color::rgb(0.95, 0.25, 0.2));
+}
+
+/// Starts building the structure of a pressed build button, paying for it up
+/// front if the stockpile can afford it.
+#[allow(clippy::too_many_arguments)]
 fn spawn_planned_structure(
     mut commands: Commands,
-    interaction_query: Query<(&Interaction, &BuildOnPress)>,
-    assets: Res<AssetsByStructureType>,
+    interaction_query: Query<(&Interaction, &BuildOnPress), Changed<Interaction>>,
+    registry: Res<StructureRegistry>,
+    mut stockpile: ResMut<Stockpile>,
     mut next_state: ResMut<NextState<GameState>>,
     mouse_position: Res<MousePosition>,
+    mouse_input: Res<Input<MouseButton>>,
+    layout: Res<HexLayout>,
+    tile_heights: TileHeights,
 ) {
+    // A button stays pressed for as long as the mouse button is held, and this
+    // doesn't run while building, so a button pressed to cancel building would
+    // still count as changed and pressed here. Only fresh clicks count.
+    if !mouse_input.just_pressed(MouseButton::Left) {
+        return;
+    }
+
     for (interaction, build_button) in interaction_query.iter() {
         if *interaction != Interaction::Pressed {
             continue;
         }
 
-        let mut structure = match build_button.0 {
-            StructureType::House => HouseBundle::from_assets(&assets.house),
+        let Some(definition) = registry.get(&build_button.0) else {
+            continue;
         };
+        if !stockpile.spend(&definition.cost) {
+            continue;
+        }
+        let mut structure = StructureDefinition::spatial_bundle();
+        let rotation = Facing::default().rotation();
+        structure.transform.translation = mouse_position.world;
+        structure.transform.rotation = rotation;
 
-        structure.pbr.transform.translation = mouse_position.world;
-
-        let planned_structure =
-            PlannedStructureBundle::from_tile_position(mouse_position.tile.round());
+        let hovered_tile = HexCoord::from(&mouse_position.tile);
+        let tile_height = tile_heights.get(hovered_tile).unwrap_or_default();
+        let planned_structure = PlannedStructureBundle::from_hex_coord(
+            hovered_tile,
+            definition.cost,
+            rotation,
+            &layout,
+            tile_height,
+        );
 
-        commands.spawn((structure, planned_structure));
+        commands.spawn((structure, build_button.0.clone(), planned_structure));
         next_state.set(GameState::Building);
     }
 }
@@ -89,52 +189,197 @@ fn cleanup(mut commands: Commands, structure_query: Query<Entity, With<PlannedSt
 }
 
 fn follow_mouse(
-    mut house_query: Query<(&mut TilePosition, &mut ScaleSpring), With<PlannedStructure>>,
-    tile_query: Query<&TilePosition, (With<Tile>, Without<PlannedStructure>)>,
+    mut house_query: Query<
+        (&mut HexCoord, &mut ScaleSpring, &mut TranslationSpring),
+        With<PlannedStructure>,
+    >,
+    tile_heights: TileHeights,
     mouse_position: Res<MousePosition>,
 ) {
-    let hovered_tile = mouse_position.tile.round();
-    let is_on_tile = tile_query
+    let hovered_tile = HexCoord::from(&mouse_position.tile);
+    let tile_height = tile_heights.get(hovered_tile);
+
+    for (mut hex_coord, mut scale_spring, mut translation_spring) in house_query.iter_mut() {
+        *hex_coord = hovered_tile;
+        scale_spring.target = if tile_height.is_some() {
+            Vec3::ONE
+        } else {
+            Vec3::ZERO
+        };
+        if let Some(tile_height) = tile_height {
+            translation_spring.target.y = tile_height + PLANNED_STRUCTURE_HOVER_HEIGHT;
+        }
+    }
+}
+
+/// Turns planned structures through the six hex directions, clockwise as seen
+/// from above with E or by scrolling down, and counter-clockwise with Q or by
+/// scrolling up.
+fn rotate_planned_structure(
+    mut structure_query: Query<(&mut Facing, &mut RotationSpring), With<PlannedStructure>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut mouse_wheel_events: EventReader<MouseWheel>,
+) {
+    // A step of +1 turns counter-clockwise as seen from above
+    let mut steps = 0;
+    if keyboard_input.just_pressed(KeyCode::Q) {
+        steps += 1;
+    }
+    if keyboard_input.just_pressed(KeyCode::E) {
+        steps -= 1;
+    }
+    for event in mouse_wheel_events.read() {
+        steps += event.y.signum() as i32;
+    }
+    if steps == 0 {
+        return;
+    }
+
+    for (mut facing, mut rotation_spring) in structure_query.iter_mut() {
+        *facing = facing.rotated(steps);
+        rotation_spring.target = facing.angle();
+    }
+}
+
+fn check_placement(
+    mut structure_query: Query<
+        (&StructureId, &HexCoord, &Facing, &mut PlacementCheck),
+        With<PlannedStructure>,
+    >,
+    registry: Res<StructureRegistry>,
+    rules: Res<PlacementRules>,
+    context: PlacementContext,
+) {
+    for (id, coord, facing, mut placement_check) in structure_query.iter_mut() {
+        let Some(definition) = registry.get(id) else {
+            continue;
+        };
+        let tiles: Vec<_> = definition.tiles(*coord, *facing).collect();
+        let result = rules.check(id, &tiles, &context);
+        if placement_check.0 != result {
+            placement_check.0 = result;
+        }
+    }
+}
+
+/// Shows planned structures as ghosts tinted green where they can be placed
+/// and red where they can't.
+fn tint_planned_structure(
+    mut commands: Commands,
+    structure_query: Query<
+        (Entity, &PlacementCheck),
+        (With<PlannedStructure>, Changed<PlacementCheck>),
+    >,
+    ghost_materials: Res<GhostMaterials>,
+) {
+    for (entity, placement_check) in structure_query.iter() {
+        let ghost_material = match placement_check.0 {
+            Ok(()) => &ghost_materials.valid,
+            Err(_) => &ghost_materials.invalid,
+        };
+        commands.entity(entity).insert(MaterialOverride {
+            material: ghost_material.clone(),
+            casts_shadows: false,
+        });
+    }
+}
+
+/// Stops building when the right mouse button or Escape is pressed, or when
+/// the button of the planned structure is pressed again, shrinking the planned
+/// structure away and refunding its cost.
+fn cancel_building(
+    mut commands: Commands,
+    mut structure_query: Query<
+        (Entity, &StructureId, &ReservedGoods, &mut ScaleSpring),
+        With<PlannedStructure>,
+    >,
+    interaction_query: Query<(&Interaction, &BuildOnPress), Changed<Interaction>>,
+    mut stockpile: ResMut<Stockpile>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mouse_input: Res<Input<MouseButton>>,
+    keyboard_input: Res<Input<KeyCode>>,
+) {
+    // The button that started building still counts as changed and pressed
+    // the first time this runs, so only fresh clicks count
+    let clicked = mouse_input.just_pressed(MouseButton::Left);
+    let pressed_structures: Vec<_> = interaction_query
         .iter()
-        .any(|tile_position| *tile_position == hovered_tile);
+        .filter(|(interaction, _)| clicked && **interaction == Interaction::Pressed)
+        .map(|(_, build_button)| &build_button.0)
+        .collect();
+    let cancel_all = mouse_input.just_pressed(MouseButton::Right)
+        || keyboard_input.just_pressed(KeyCode::Escape);
 
-    for (mut tile_position, mut scale_spring) in house_query.iter_mut() {
-        *tile_position = hovered_tile.clone();
-        scale_spring.target = if is_on_tile { Vec3::ONE } else { Vec3::ZERO };
+    let mut cancelled = false;
+    for (entity, id, reserved_goods, mut scale_spring) in structure_query.iter_mut() {
+        if !cancel_all && !pressed_structures.contains(&id) {
+            continue;
+        }
+        stockpile.add(&reserved_goods.0);
+        // It's no longer a structure at all, so it isn't placed or saved while
+        // it shrinks
+        scale_spring.target = Vec3::ZERO;
+        commands
+            .entity(entity)
+            .remove::<(PlannedStructure, StructureId, HexCoord, ReservedGoods)>()
+            .insert(DespawnWhenShrunk);
+        cancelled = true;
+    }
+
+    if cancelled {
+        next_state.set(GameState::Playing);
     }
 }
 
 fn build_structure(
     mut commands: Commands,
-    state: Res<State<GameState>>,
     mut next_state: ResMut<NextState<GameState>>,
     mut structure_query: Query<
-        (Entity, &mut TilePosition, &mut TranslationSpring),
+        (
+            Entity,
+            &StructureId,
+            &HexCoord,
+            &Facing,
+            &PlacementCheck,
+            &mut TranslationSpring,
+        ),
         With<PlannedStructure>,
     >,
-    tile_query: Query<&TilePosition, (With<Tile>, Without<PlannedStructure>)>,
-    mouse_position: Res<MousePosition>,
+    button_query: Query<&Interaction, With<Button>>,
+    mut history: ResMut<CommandHistory>,
+    tile_heights: TileHeights,
     mouse_input: Res<Input<MouseButton>>,
 ) {
-    if *state.get() != GameState::Building || !mouse_input.just_pressed(MouseButton::Left) {
+    if !mouse_input.just_pressed(MouseButton::Left) {
         return;
     }
-
-    let hovered_tile = mouse_position.tile.round();
-    let is_on_tile = tile_query
+    // Clicks on the HUD aren't meant for the tiles behind it
+    if button_query
         .iter()
-        .any(|tile_position| *tile_position == hovered_tile);
-    if !is_on_tile {
+        .any(|interaction| *interaction != Interaction::None)
+    {
         return;
     }
 
-    for (structure_entity, mut tile_position, mut translation_spring) in structure_query.iter_mut()
+    for (structure_entity, id, hex_coord, facing, placement_check, mut translation_spring) in
+        structure_query.iter_mut()
     {
-        *tile_position = hovered_tile.clone();
-        translation_spring.target.y = 0.;
+        let Some(tile_height) = tile_heights.get(*hex_coord) else {
+            return;
+        };
+        if placement_check.0.is_err() {
+            return;
+        }
+
+        translation_spring.target.y = tile_height;
         commands
             .entity(structure_entity)
-            .remove::<PlannedStructure>();
+            .remove::<(PlannedStructure, ReservedGoods, MaterialOverride)>();
+        history.push(PlaceStructure {
+            structure: id.clone(),
+            coord: *hex_coord,
+            facing: *facing,
+        });
     }
 
     next_state.set(GameState::Playing);
@@ -0,0 +1,339 @@
+use std::{any::TypeId, collections::HashMap, error::Error, fmt, path::PathBuf};
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    gltf::Gltf,
+    prelude::*,
+    utils::BoxedFuture,
+};
+use bevy_asset_loader::prelude::*;
+use serde::Deserialize;
+
+use crate::{prelude::*, stockpile::Goods};
+
+use super::{placement::PlacementRuleDefinition, Facing, StructureAssets, StructureId};
+
+pub struct StructureRegistryPlugin;
+
+impl Plugin for StructureRegistryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<StructureDefinition>()
+            .register_asset_loader(StructureDefinitionLoader)
+            .init_resource::<StructureRegistry>()
+            .add_collection_to_loading_state::<_, StructureDefinitions>(AppState::Loading)
+            .add_collection_to_loading_state::<_, StructureModels>(AppState::Loading)
+            .add_systems(OnExit(AppState::Loading), build_structure_registry);
+    }
+}
+
+/// Everything about one kind of structure that can be built, loaded from a
+/// `.structure.ron` file in `assets/structures`:
+///
+/// ```ron
+/// (
+///     id: "house",
+///     name: "House",
+///     model: Box(size: (0.4, 0.4, 0.4), color: (1.0, 0.84, 0.0)),
+///     scene: Some((path: "models/house.gltf", scale: 0.18)),
+///     rotation: -30,
+///     cost: (wood: 10, stone: 2),
+///     footprint: [(q: 0, r: 0)],
+///     placement: [TileIsFree, BuildableTerrain],
+/// )
+/// ```
+#[derive(Asset, TypePath, Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StructureDefinition {
+    pub id: StructureId,
+    /// The name shown to players, such as on its build button.
+    pub name: String,
+    /// The asset path of an image shown on its build button instead of the
+    /// name.
+    #[serde(default)]
+    pub icon: Option<String>,
+    pub model: StructureModel,
+    /// A glTF model shown instead of `model`, which is kept as a fallback for
+    /// when the file is missing.
+    #[serde(default)]
+    pub scene: Option<StructureScene>,
+    /// The rotation of the model around the vertical axis when the structure
+    /// faces its default direction, in degrees.
+    #[serde(default)]
+    pub rotation: f32,
+    /// The goods taken from the stockpile to build the structure.
+    #[serde(default)]
+    pub cost: Goods,
+    /// The tiles the structure covers, as offsets from the tile it stands on
+    /// when it faces its default direction. Always includes `(q: 0, r: 0)`.
+    #[serde(default = "default_footprint")]
+    pub footprint: Vec<HexCoord>,
+    /// The rules the structure must follow to be placed.
+    #[serde(default)]
+    pub placement: Vec<PlacementRuleDefinition>,
+    /// Whether the structure is tall enough to block line of sight through
+    /// the tiles it covers.
+    #[serde(default)]
+    pub blocks_sight: bool,
+    /// The assets built from `model` when the registry is built.
+    #[serde(skip)]
+    pub assets: StructureAssets,
+}
+
+fn default_footprint() -> Vec<HexCoord> {
+    vec![HexCoord::ZERO]
+}
+
+/// How a structure looks when it has no glTF `scene`, built in code.
+#[derive(Debug, Clone, Deserialize)]
+pub enum StructureModel {
+    /// A box standing on the center of the tile, with its size in world units
+    /// and its color as RGB.
+    Box {
+        size: (f32, f32, f32),
+        color: (f32, f32, f32),
+    },
+}
+
+/// A glTF model of a structure, with its default scene placed on the center of
+/// the tile.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StructureScene {
+    /// The asset path of a `.gltf` or `.glb` file in `assets/models`.
+    pub path: String,
+    /// How much the model is scaled, to fit it onto a tile.
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+    /// How far the model is moved from the center of the tile, in world units
+    /// and before it's turned, to line it up with the tile.
+    #[serde(default)]
+    pub offset: (f32, f32, f32),
+}
+
+fn default_scale() -> f32 {
+    1.
+}
+
+impl StructureDefinition {
+    /// The transform of the structure's model relative to the center of its
+    /// tile, when it faces its default direction.
+    pub fn model_transform(&self) -> Transform {
+        let transform = Transform::from_rotation(Quat::from_rotation_y(self.rotation.to_radians()));
+        match (&self.assets, &self.scene) {
+            (StructureAssets::Scene(_), Some(scene)) => transform
+                .with_translation(scene.offset.into())
+                .with_scale(Vec3::splat(scene.scale)),
+            _ => transform,
+        }
+    }
+
+    /// The tiles covered by the structure when it stands on `coord`, facing
+    /// `facing`.
+    pub fn tiles(&self, coord: HexCoord, facing: Facing) -> impl Iterator<Item = HexCoord> + '_ {
+        self.footprint
+            .iter()
+            .map(move |offset| coord + offset.rotate_around(HexCoord::ZERO, facing.steps()))
+    }
+
+    /// A bundle for a new structure of this kind, which starts out shrunk to
+    /// nothing so that it can pop up. Its model is added as a child by
+    /// `spawn_structure_models`.
+    pub fn spatial_bundle() -> SpatialBundle {
+        SpatialBundle::from_transform(Transform::from_scale(Vec3::ZERO))
+    }
+}
+
+/// A structure definition that couldn't be loaded, and why.
+#[derive(Debug)]
+pub struct StructureDefinitionError {
+    pub path: PathBuf,
+    pub kind: StructureDefinitionErrorKind,
+}
+
+#[derive(Debug)]
+pub enum StructureDefinitionErrorKind {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+    /// The footprint doesn't include the tile the structure stands on.
+    FootprintWithoutOrigin,
+}
+
+impl fmt::Display for StructureDefinitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = self.path.display();
+        match &self.kind {
+            StructureDefinitionErrorKind::Io(error) => {
+                write!(f, "{path}: could not read structure definition: {error}")
+            }
+            StructureDefinitionErrorKind::Ron(error) => write!(
+                f,
+                "{path}:{}:{}: {}",
+                error.position.line, error.position.col, error.code
+            ),
+            StructureDefinitionErrorKind::FootprintWithoutOrigin => {
+                write!(f, "{path}: footprint must include (q: 0, r: 0)")
+            }
+        }
+    }
+}
+
+impl Error for StructureDefinitionError {}
+
+/// Loads `StructureDefinition` assets from `.structure.ron` files.
+struct StructureDefinitionLoader;
+
+impl AssetLoader for StructureDefinitionLoader {
+    type Asset = StructureDefinition;
+    type Settings = ();
+    type Error = StructureDefinitionError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<StructureDefinition, StructureDefinitionError>> {
+        Box::pin(async move {
+            let path = load_context.path().to_path_buf();
+            let error = |kind| StructureDefinitionError {
+                path: path.clone(),
+                kind,
+            };
+
+            let mut text = String::new();
+            reader
+                .read_to_string(&mut text)
+                .await
+                .map_err(|io_error| error(StructureDefinitionErrorKind::Io(io_error)))?;
+
+            let definition: StructureDefinition = ron::from_str(&text)
+                .map_err(|ron_error| error(StructureDefinitionErrorKind::Ron(ron_error)))?;
+            if !definition.footprint.contains(&HexCoord::ZERO) {
+                return Err(error(StructureDefinitionErrorKind::FootprintWithoutOrigin));
+            }
+            Ok(definition)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["structure.ron"]
+    }
+}
+
+/// The structure definitions in `assets/structures`, loaded during
+/// `AppState::Loading`.
+#[derive(AssetCollection, Resource)]
+pub struct StructureDefinitions {
+    #[asset(path = "structures", collection(typed))]
+    pub definitions: Vec<Handle<StructureDefinition>>,
+}
+
+/// The glTF models in `assets/models`, by their asset path such as
+/// `"models/house.gltf"`. Other files in the folder are ignored.
+#[derive(AssetCollection, Resource)]
+pub struct StructureModels {
+    #[asset(path = "models", collection(mapped))]
+    pub models: bevy::utils::HashMap<String, UntypedHandle>,
+}
+
+impl StructureModels {
+    pub fn get(&self, path: &str) -> Option<Handle<Gltf>> {
+        let handle = self.models.get(path)?;
+        (handle.type_id() == TypeId::of::<Gltf>()).then(|| handle.clone().typed())
+    }
+}
+
+/// All kinds of structures that can be built, in the order they're shown in
+/// the HUD.
+#[derive(Resource, Default)]
+pub struct StructureRegistry {
+    structures: Vec<StructureDefinition>,
+    indices: HashMap<StructureId, usize>,
+}
+
+impl StructureRegistry {
+    pub fn get(&self, id: &StructureId) -> Option<&StructureDefinition> {
+        self.indices.get(id).map(|index| &self.structures[*index])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &StructureDefinition> {
+        self.structures.iter()
+    }
+
+    pub(super) fn insert(&mut self, structure: StructureDefinition) {
+        match self.indices.get(&structure.id) {
+            Some(index) => {
+                warn!(
+                    "Replacing duplicate structure definition {:?}",
+                    structure.id
+                );
+                self.structures[*index] = structure;
+            }
+            None => {
+                self.indices
+                    .insert(structure.id.clone(), self.structures.len());
+                self.structures.push(structure);
+            }
+        }
+    }
+}
+
+pub fn build_structure_registry(
+    mut registry: ResMut<StructureRegistry>,
+    definition_handles: Res<StructureDefinitions>,
+    definitions: Res<Assets<StructureDefinition>>,
+    models: Res<StructureModels>,
+    gltfs: Res<Assets<Gltf>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let mut loaded: Vec<_> = definition_handles
+        .definitions
+        .iter()
+        .filter_map(|handle| definitions.get(handle))
+        .collect();
+    loaded.sort_by(|a, b| a.name.cmp(&b.name));
+
+    *registry = StructureRegistry::default();
+    for definition in loaded {
+        let scene = definition.scene.as_ref().and_then(|scene| {
+            let gltf = models.get(&scene.path).and_then(|gltf| gltfs.get(gltf));
+            let default_scene =
+                gltf.and_then(|gltf| gltf.default_scene.clone().or(gltf.scenes.first().cloned()));
+            if default_scene.is_none() {
+                warn!(
+                    "Could not find the model {:?} of {:?}, using its fallback model instead",
+                    scene.path, definition.id
+                );
+            }
+            default_scene
+        });
+
+        let assets = match (scene, &definition.model) {
+            (Some(scene), _) => StructureAssets::Scene(scene),
+            (
+                None,
+                StructureModel::Box {
+                    size: (width, height, depth),
+                    color: (red, green, blue),
+                },
+            ) => {
+                let shape = shape::Box::from_corners(
+                    Vec3::new(-width / 2., 0., -depth / 2.),
+                    Vec3::new(width / 2., *height, depth / 2.),
+                );
+                StructureAssets::Mesh {
+                    mesh: meshes.add(shape.into()),
+                    material: materials.add(StandardMaterial {
+                        base_color: Color::rgb(*red, *green, *blue),
+                        ..default()
+                    }),
+                }
+            }
+        };
+        registry.insert(StructureDefinition {
+            assets,
+            ..definition.clone()
+        });
+    }
+}
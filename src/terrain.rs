@@ -0,0 +1,131 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The kind of ground a tile is made of, which decides how it looks and what
+/// can be done with it.
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Terrain {
+    #[default]
+    Grass,
+    Forest,
+    Water,
+    Rock,
+    Sand,
+}
+
+/// The goods a tile produces when worked.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Yield {
+    pub food: u32,
+    pub wood: u32,
+    pub stone: u32,
+}
+
+/// The gameplay and rendering properties shared by all tiles of a terrain
+/// type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TerrainProperties {
+    pub name: &'static str,
+    /// Whether structures can be placed on tiles of this terrain.
+    pub buildable: bool,
+    /// The cost of walking onto a tile of this terrain, or `None` if it can't
+    /// be walked on.
+    pub movement_cost: Option<u32>,
+    /// Whether tiles of this terrain block line of sight, like mountains.
+    pub blocks_sight: bool,
+    pub tile_yield: Yield,
+    pub color: Color,
+}
+
+const GRASS: TerrainProperties = TerrainProperties {
+    name: "Grass",
+    buildable: true,
+    movement_cost: Some(1),
+    blocks_sight: false,
+    tile_yield: Yield {
+        food: 2,
+        wood: 0,
+        stone: 0,
+    },
+    color: Color::rgb(0.36, 0.6, 0.25),
+};
+
+const FOREST: TerrainProperties = TerrainProperties {
+    name: "Forest",
+    buildable: false,
+    movement_cost: Some(2),
+    blocks_sight: false,
+    tile_yield: Yield {
+        food: 1,
+        wood: 2,
+        stone: 0,
+    },
+    color: Color::rgb(0.13, 0.36, 0.16),
+};
+
+const WATER: TerrainProperties = TerrainProperties {
+    name: "Water",
+    buildable: false,
+    movement_cost: None,
+    blocks_sight: false,
+    tile_yield: Yield {
+        food: 1,
+        wood: 0,
+        stone: 0,
+    },
+    color: Color::rgb(0.2, 0.42, 0.7),
+};
+
+const ROCK: TerrainProperties = TerrainProperties {
+    name: "Rock",
+    buildable: false,
+    movement_cost: Some(3),
+    blocks_sight: true,
+    tile_yield: Yield {
+        food: 0,
+        wood: 0,
+        stone: 2,
+    },
+    color: Color::rgb(0.5, 0.5, 0.52),
+};
+
+const SAND: TerrainProperties = TerrainProperties {
+    name: "Sand",
+    buildable: true,
+    movement_cost: Some(1),
+    blocks_sight: false,
+    tile_yield: Yield {
+        food: 0,
+        wood: 0,
+        stone: 1,
+    },
+    color: Color::rgb(0.86, 0.78, 0.5),
+};
+
+impl Terrain {
+    pub fn properties(self) -> &'static TerrainProperties {
+        match self {
+            Terrain::Grass => &GRASS,
+            Terrain::Forest => &FOREST,
+            Terrain::Water => &WATER,
+            Terrain::Rock => &ROCK,
+            Terrain::Sand => &SAND,
+        }
+    }
+
+    pub fn is_buildable(self) -> bool {
+        self.properties().buildable
+    }
+
+    pub fn movement_cost(self) -> Option<u32> {
+        self.properties().movement_cost
+    }
+
+    pub fn blocks_sight(self) -> bool {
+        self.properties().blocks_sight
+    }
+
+    pub fn color(self) -> Color {
+        self.properties().color
+    }
+}
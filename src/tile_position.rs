@@ -1,26 +1,14 @@
-use crate::{prelude::*, spring::TranslationSpring};
-use bevy::prelude::*;
+use std::ops::{Add, Sub};
 
-pub struct TilePositionPlugin;
+use crate::prelude::*;
 
-impl Plugin for TilePositionPlugin {
-    fn build(&self, app: &mut App) {
-        app.add_systems(Update, set_tile_position.run_if(in_state(AppState::InGame)));
-    }
-}
-
-#[derive(Component, Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct TilePosition {
     pub q: f32,
     pub r: f32,
 }
 
-const SQRT_3: f32 = 1.7320508;
-
 impl TilePosition {
-    pub const CIRCUMRADIUS: f32 = 0.5;
-    pub const INRADIUS: f32 = SQRT_3 * Self::CIRCUMRADIUS / 2.0;
-
     /// A zero tile vector.
     pub const ZERO: Self = Self::new(0., 0.);
 
@@ -42,34 +30,34 @@ impl TilePosition {
     /// A tile vector pointing one tile "north-west".
     pub const NW: Self = Self::new(-1., 0.);
 
-    /// The "north" tile vector as a `Vec2` in world space.
-    pub const N_VEC2: Vec2 = Vec2::new(0., Self::INRADIUS * 2.0);
-
-    /// The "north-east" tile vector as a `Vec2` in world space.
-    pub const NE_VEC2: Vec2 = Vec2::new(Self::CIRCUMRADIUS, Self::INRADIUS);
-
-    /// The "south-east" tile vector as a `Vec2` in world space.
-    pub const SE_VEC2: Vec2 = Vec2::new(Self::CIRCUMRADIUS, -Self::INRADIUS);
-
-    /// The "south" tile vector as a `Vec2` in world space.
-    pub const S_VEC2: Vec2 = Vec2::new(0., -Self::INRADIUS * 2.0);
-
-    /// The "south-west" tile vector as a `Vec2` in world space.
-    pub const SW_VEC2: Vec2 = Vec2::new(-Self::CIRCUMRADIUS, -Self::INRADIUS);
-
-    /// The "north-west" tile vector as a `Vec2` in world space.
-    pub const NW_VEC2: Vec2 = Vec2::new(-Self::CIRCUMRADIUS, Self::INRADIUS);
-
-    /// The Q basis vector as a `Vec2` in world space.
-    pub const Q_VEC2: Vec2 = Vec2::new(3. / 2., SQRT_3 / 2.);
-
-    /// The R basis vector as a `Vec2` in world space.
-    pub const R_VEC2: Vec2 = Vec2::new(0., SQRT_3);
+    /// The six tile direction vectors, clockwise starting from "north". The
+    /// index of a direction in this array is its direction index.
+    pub const DIRECTIONS: [Self; 6] = [Self::N, Self::NE, Self::SE, Self::S, Self::SW, Self::NW];
 
     pub const fn new(q: f32, r: f32) -> Self {
         Self { q, r }
     }
 
+    /// The tile vector for a direction index. Indices wrap around, so 6 is
+    /// "north" again and -1 is "north-west".
+    pub fn from_direction(index: i32) -> Self {
+        Self::DIRECTIONS[index.rem_euclid(6) as usize].clone()
+    }
+
+    /// The index of the direction this tile vector points closest to, or `None`
+    /// for the zero vector.
+    pub fn direction(&self) -> Option<i32> {
+        // The angles between directions are the same in every layout, so the
+        // default one is good enough for finding the closest direction
+        let layout = HexLayout::default();
+        let pos = layout.tile_to_world(self).try_normalize()?;
+        (0..6).max_by(|a, b| {
+            let a_dot = pos.dot(layout.direction_vec2(*a));
+            let b_dot = pos.dot(layout.direction_vec2(*b));
+            a_dot.total_cmp(&b_dot)
+        })
+    }
+
     pub fn s(&self) -> f32 {
         -self.q - self.r
     }
@@ -91,96 +79,28 @@ impl TilePosition {
         Self { q, r }
     }
 
-    pub fn corners(&self) -> impl Iterator<Item = Vec2> + '_ {
-        let center = Vec2::from(self.round());
-        (0..6).map(move |i| {
-            let angle = std::f32::consts::PI / 3.0 * i as f32;
-            center + Vec2::new(angle.cos(), angle.sin()) * Self::CIRCUMRADIUS
-        })
-    }
-
-    pub fn to_vec3_with_y(&self, y: f32) -> Vec3 {
-        let pos = Vec2::from(self);
-        Vec3::new(pos.x, y, -pos.y)
+    /// Linearly interpolate between this position and another, where a `t` of
+    /// 0 gives this position and 1 gives `other`.
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self::new(
+            self.q + (other.q - self.q) * t,
+            self.r + (other.r - self.r) * t,
+        )
     }
 }
 
-impl<'a> From<&'a TilePosition> for Vec2 {
-    fn from(TilePosition { q, r }: &'a TilePosition) -> Self {
-        (TilePosition::Q_VEC2 * *q + TilePosition::R_VEC2 * *r) * TilePosition::CIRCUMRADIUS
-    }
-}
-
-impl From<TilePosition> for Vec2 {
-    /// Translate a tile position in the hexagonal grid into a world position
-    fn from(pos: TilePosition) -> Vec2 {
-        Vec2::from(&pos)
-    }
-}
-
-impl<'a> From<&'a TilePosition> for Vec3 {
-    fn from(pos: &'a TilePosition) -> Vec3 {
-        pos.to_vec3_with_y(0.)
-    }
-}
-
-impl From<TilePosition> for Vec3 {
-    fn from(pos: TilePosition) -> Vec3 {
-        Vec3::from(&pos)
-    }
-}
-
-impl<'a> From<&'a Vec2> for TilePosition {
-    /// Translate a world position into a tile position in the hexagonal grid
-    fn from(Vec2 { x, y }: &'a Vec2) -> Self {
-        let q = (2. / 3. * x) / Self::CIRCUMRADIUS;
-        let r = (-1. / 3. * x + SQRT_3 / 3. * y) / Self::CIRCUMRADIUS;
-        TilePosition::new(q, r)
-    }
-}
-
-impl From<Vec2> for TilePosition {
-    fn from(value: Vec2) -> Self {
-        TilePosition::from(&value)
-    }
-}
+impl Add for TilePosition {
+    type Output = Self;
 
-impl<'a> From<&'a Vec3> for TilePosition {
-    fn from(Vec3 { x, z, .. }: &'a Vec3) -> Self {
-        Vec2::new(*x, -*z).into()
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.q + rhs.q, self.r + rhs.r)
     }
 }
 
-impl From<Vec3> for TilePosition {
-    fn from(Vec3 { x, z, .. }: Vec3) -> Self {
-        Vec2::new(x, -z).into()
-    }
-}
+impl Sub for TilePosition {
+    type Output = Self;
 
-/// Updates the position of any entity with a tile position and a transform
-/// component to the center of the tile position's world space equivalent. Only
-/// runs when the tile position changes.
-pub fn set_tile_position(
-    mut query: Query<
-        (
-            &mut Transform,
-            &TilePosition,
-            Option<&mut TranslationSpring>,
-        ),
-        Changed<TilePosition>,
-    >,
-) {
-    for (mut transform, tile_position, translation_spring) in query.iter_mut() {
-        let Vec2 { x, y } = Vec2::from(tile_position);
-        match translation_spring {
-            None => {
-                transform.translation.x = x;
-                transform.translation.z = -y;
-            }
-            Some(mut translation_spring) => {
-                translation_spring.target.x = x;
-                translation_spring.target.z = -y;
-            }
-        }
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.q - rhs.q, self.r - rhs.r)
     }
 }
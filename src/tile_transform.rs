@@ -0,0 +1,37 @@
+use bevy::prelude::*;
+
+use crate::{prelude::*, spring::TranslationSpring};
+
+pub struct TileTransformPlugin;
+
+impl Plugin for TileTransformPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HexLayout>()
+            .add_systems(Update, set_tile_position.run_if(in_state(AppState::InGame)));
+    }
+}
+
+/// Updates the position of any entity with a hex coordinate and a transform
+/// component to the center of the tile's world space equivalent. Only runs when
+/// the hex coordinate changes.
+pub fn set_tile_position(
+    mut query: Query<
+        (&mut Transform, &HexCoord, Option<&mut TranslationSpring>),
+        Changed<HexCoord>,
+    >,
+    layout: Res<HexLayout>,
+) {
+    for (mut transform, hex_coord, translation_spring) in query.iter_mut() {
+        let Vec2 { x, y } = layout.hex_to_world(*hex_coord);
+        match translation_spring {
+            None => {
+                transform.translation.x = x;
+                transform.translation.z = -y;
+            }
+            Some(mut translation_spring) => {
+                translation_spring.target.x = x;
+                translation_spring.target.z = -y;
+            }
+        }
+    }
+}
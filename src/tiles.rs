@@ -1,107 +1,223 @@
-use std::f32::consts::PI;
+use std::collections::HashMap;
 
-use crate::prelude::*;
+use crate::{
+    chunks::{tile_corners, HexMeshBuilder},
+    elevation::TileHeights,
+    hex_map::HexMap,
+    map_file::MapFile,
+    map_generation::MapGenerator,
+    map_shape::{configure_map_generator, MapAssets},
+    mouse_position::update_mouse_position,
+    prelude::*,
+    save::LoadedSave,
+};
 use bevy::prelude::*;
 
 pub struct TilesPlugin;
 
 impl Plugin for TilesPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(TileMaterials(None))
-            .add_systems(OnExit(AppState::Loading), spawn_tiles)
-            .add_systems(OnExit(AppState::InGame), despawn_tiles)
+        app.init_resource::<TileIndex>()
+            .init_resource::<MapGenerator>()
+            .add_systems(
+                OnExit(AppState::Loading),
+                (
+                    (configure_map_generator, spawn_tiles).chain(),
+                    spawn_hover_overlay,
+                ),
+            )
+            .add_systems(
+                OnExit(AppState::InGame),
+                (despawn_tiles, despawn_hover_overlay),
+            )
             .add_systems(
                 Update,
-                highlight_hovered_tile.run_if(in_state(AppState::InGame)),
+                (
+                    index_tiles,
+                    update_hover_overlay
+                        .after(index_tiles)
+                        .after(update_mouse_position),
+                )
+                    .run_if(in_state(AppState::InGame)),
             );
     }
 }
 
+/// Marks the entity holding the data of a tile. Tiles are drawn in merged
+/// chunk meshes rather than one by one, see `ChunksPlugin`.
 #[derive(Component)]
 pub struct Tile;
 
-#[derive(Resource)]
-struct TileMaterials(Option<TileMaterialHandles>);
+/// Marks the translucent hexagon drawn over the tile under the mouse cursor.
+#[derive(Component)]
+struct HoverOverlay;
+
+/// How far above the top face of the hovered tile its overlay is drawn, to
+/// avoid z-fighting.
+const HOVER_OVERLAY_OFFSET: f32 = 0.002;
 
-struct TileMaterialHandles {
-    base: Handle<StandardMaterial>,
-    hovered: Handle<StandardMaterial>,
+/// Looks up tile entities by their coordinate in constant time. Kept in sync
+/// with spawned and despawned tiles by `index_tiles`.
+#[derive(Resource, Default)]
+pub struct TileIndex {
+    tiles: HexMap<Entity>,
+    coords: HashMap<Entity, HexCoord>,
+}
+
+impl TileIndex {
+    fn new(tiles: HexMap<Entity>) -> Self {
+        Self {
+            tiles,
+            coords: HashMap::new(),
+        }
+    }
+
+    /// The tile entity at the given coordinate, if there is one.
+    pub fn get(&self, coord: HexCoord) -> Option<Entity> {
+        self.tiles.get(coord).copied()
+    }
+
+    /// Whether there is a tile at the given coordinate.
+    pub fn contains(&self, coord: HexCoord) -> bool {
+        self.tiles.contains(coord)
+    }
+
+    fn insert(&mut self, coord: HexCoord, entity: Entity) {
+        if let Some(previous) = self.tiles.insert(coord, entity) {
+            self.coords.remove(&previous);
+        }
+        self.coords.insert(entity, coord);
+    }
+
+    fn remove(&mut self, entity: Entity) {
+        let Some(coord) = self.coords.remove(&entity) else {
+            return;
+        };
+        if self.get(coord) == Some(entity) {
+            self.tiles.remove(coord);
+        }
+    }
 }
 
 fn spawn_tiles(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    mut tile_materials: ResMut<TileMaterials>,
+    mut tile_index: ResMut<TileIndex>,
+    layout: Res<HexLayout>,
+    map_generator: Res<MapGenerator>,
+    map_assets: Res<MapAssets>,
+    map_files: Res<Assets<MapFile>>,
+    loaded_save: Res<LoadedSave>,
 ) {
-    let hexagon = shape::RegularPolygon {
-        sides: 6,
-        radius: 0.49,
+    let map_file = map_assets
+        .file
+        .as_ref()
+        .and_then(|file| map_files.get(file));
+    let listed_tiles = match &loaded_save.0 {
+        Some(save) => Some(&save.tiles),
+        None => map_file.map(|map_file| &map_file.tiles),
     };
-    let hexagon_mesh = meshes.add(hexagon.into());
-    let base_material = materials.add(StandardMaterial {
-        base_color: Color::CYAN,
-        ..default()
-    });
-    let hovered_material = materials.add(StandardMaterial {
-        base_color: Color::RED,
-        ..default()
-    });
-    tile_materials.0 = Some(TileMaterialHandles {
-        base: base_material.clone(),
-        hovered: hovered_material,
-    });
-
-    let grid_size = 3;
-    for q in -grid_size..=grid_size {
-        for r in (-grid_size - q).max(-grid_size)..=(grid_size - q).min(grid_size) {
-            let pos = TilePosition::new(q as f32, r as f32);
-
-            commands.spawn((
-                PbrBundle {
-                    mesh: hexagon_mesh.clone(),
-                    material: base_material.clone(),
-                    transform: Transform::from_translation((&pos).into()).with_rotation(
-                        Quat::from_rotation_x(PI / -2.) * Quat::from_rotation_z(PI / 2.),
-                    ),
-                    ..default()
-                },
-                pos.clone(),
-                Tile,
-            ));
+    let tiles: Vec<_> = match listed_tiles {
+        Some(listed_tiles) => {
+            *tile_index = TileIndex::default();
+            listed_tiles
+                .iter()
+                .map(|tile| (tile.coord, tile.terrain, tile.elevation, tile.deposit))
+                .collect()
+        }
+        None => {
+            *tile_index = TileIndex::new(map_generator.shape.new_map(layout.orientation));
+            map_generator
+                .generate(layout.orientation)
+                .iter()
+                .map(|(coord, tile)| (coord, tile.terrain, tile.elevation_steps(), tile.deposit))
+                .collect()
+        }
+    };
+
+    for (coord, terrain, elevation, deposit) in tiles {
+        let mut tile = commands.spawn((coord, terrain, elevation, Tile));
+        if let Some(deposit) = deposit {
+            tile.insert(deposit);
         }
     }
 }
 
+fn spawn_hover_overlay(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    layout: Res<HexLayout>,
+) {
+    let mut builder = HexMeshBuilder::default();
+    builder.add_prism(Vec2::ZERO, &tile_corners(&layout), 0., Color::WHITE);
+
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(builder.build()),
+            material: materials.add(StandardMaterial {
+                base_color: Color::rgba(1., 1., 1., 0.3),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            }),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        HoverOverlay,
+    ));
+}
+
 fn despawn_tiles(
     mut commands: Commands,
     query: Query<Entity, With<Tile>>,
-    mut tile_materials: ResMut<TileMaterials>,
+    mut tile_index: ResMut<TileIndex>,
 ) {
     for entity in query.iter() {
         commands.entity(entity).despawn_recursive();
     }
 
-    tile_materials.0 = None;
+    *tile_index = TileIndex::default();
 }
 
-fn highlight_hovered_tile(
-    mut material_query: Query<(&mut Handle<StandardMaterial>, &TilePosition), With<Tile>>,
-    tile_materials: Res<TileMaterials>,
+fn despawn_hover_overlay(mut commands: Commands, overlay_query: Query<Entity, With<HoverOverlay>>) {
+    for overlay_entity in overlay_query.iter() {
+        commands.entity(overlay_entity).despawn_recursive();
+    }
+}
+
+/// Keeps the tile index up to date as tiles are spawned and despawned.
+pub fn index_tiles(
+    added_query: Query<(Entity, &HexCoord), Added<Tile>>,
+    mut removed_tiles: RemovedComponents<Tile>,
+    mut tile_index: ResMut<TileIndex>,
+) {
+    for entity in removed_tiles.read() {
+        tile_index.remove(entity);
+    }
+
+    for (entity, coord) in added_query.iter() {
+        tile_index.insert(*coord, entity);
+    }
+}
+
+/// Moves the hover overlay onto the top face of the tile under the mouse
+/// cursor, and hides it when the cursor isn't over a tile.
+fn update_hover_overlay(
+    mut overlay_query: Query<(&mut Transform, &mut Visibility), With<HoverOverlay>>,
+    tile_heights: TileHeights,
     mouse_position: Res<MousePosition>,
+    layout: Res<HexLayout>,
 ) {
-    let hovered_tile = mouse_position.tile.round();
-    let Some(tile_material_handles) = tile_materials.0.as_ref() else {
-        warn!("Missing tile material handles in resource");
-        return;
-    };
+    let hovered_tile = HexCoord::from(&mouse_position.tile);
+    let tile_height = tile_heights.get(hovered_tile);
 
-    for (mut material_handle, pos) in material_query.iter_mut() {
-        let is_hovered = *pos == hovered_tile;
-        *material_handle = if is_hovered {
-            tile_material_handles.hovered.clone()
-        } else {
-            tile_material_handles.base.clone()
+    for (mut transform, mut visibility) in overlay_query.iter_mut() {
+        let Some(tile_height) = tile_height else {
+            *visibility = Visibility::Hidden;
+            continue;
         };
+        *visibility = Visibility::Visible;
+        transform.translation =
+            layout.hex_to_vec3_with_y(hovered_tile, tile_height + HOVER_OVERLAY_OFFSET);
     }
 }
@@ -19,6 +19,8 @@ pub struct BuildButton;
 pub struct BuildButtonBundle {
     pub button: ButtonBundle,
     pub colors: BuildButtonColors,
+    pub active_in: ActiveInState,
+    pub disabled: Disabled,
     build_button: BuildButton,
 }
 
@@ -39,11 +41,29 @@ impl Default for BuildButtonBundle {
                 ..default()
             },
             colors: button_colors,
+            active_in: ActiveInState(GameState::Building),
+            disabled: Disabled(false),
             build_button: BuildButton,
         }
     }
 }
 
+impl BuildButtonBundle {
+    pub fn with_active_state(mut self, state: GameState) -> Self {
+        self.active_in = ActiveInState(state);
+        self
+    }
+}
+
+/// The game state in which a button is shown as switched on.
+#[derive(Component)]
+pub struct ActiveInState(pub GameState);
+
+/// Whether a button is grayed out, such as when the player can't afford what
+/// it builds.
+#[derive(Component)]
+pub struct Disabled(pub bool);
+
 #[derive(Component)]
 pub struct BuildButtonLabel;
 
@@ -77,6 +97,11 @@ impl BuildButtonLabelBundle {
         default_section.style.font = font.clone();
         bundle
     }
+
+    pub fn with_font_size(mut self, font_size: f32) -> Self {
+        self.text.text.sections[0].style.font_size = font_size;
+        self
+    }
 }
 
 fn update_button(
@@ -85,6 +110,8 @@ fn update_button(
             &Interaction,
             &mut BackgroundColor,
             &BuildButtonColors,
+            &ActiveInState,
+            &Disabled,
             &Children,
         ),
         With<BuildButton>,
@@ -92,9 +119,12 @@ fn update_button(
     mut label_query: Query<&mut Text, With<BuildButtonLabel>>,
     game_state: Res<State<GameState>>,
 ) {
-    for (interaction, mut background_color, colors, children) in &mut button_query {
+    for (interaction, mut background_color, colors, active_in, disabled, children) in
+        &mut button_query
+    {
         let (bg, fg) = match (*interaction, game_state.get()) {
-            (_, GameState::Building) => colors.on,
+            (_, state) if *state == active_in.0 => colors.on,
+            _ if disabled.0 => colors.disabled,
             (Interaction::Hovered, _) => colors.hovered,
             (Interaction::Pressed, _) => colors.pressed,
             (Interaction::None, _) => colors.off,
@@ -117,6 +147,7 @@ pub struct BuildButtonColors {
     pub on: (Color, Color),
     pub hovered: (Color, Color),
     pub pressed: (Color, Color),
+    pub disabled: (Color, Color),
 }
 
 impl BuildButtonColors {
@@ -132,6 +163,7 @@ impl Default for BuildButtonColors {
             on: (Color::BLUE, Color::WHITE),
             hovered: (Self::BG.with_l(base_lightness * 1.5), Color::WHITE),
             pressed: (Self::BG.with_l(base_lightness / 2.), Color::WHITE),
+            disabled: (Color::rgb(0.25, 0.25, 0.25), Color::rgb(0.55, 0.55, 0.55)),
         }
     }
 }
@@ -1,15 +1,34 @@
 use bevy::prelude::*;
 
-use crate::{state::AppState, structure::StructureType};
+use crate::{
+    state::{AppState, GameState},
+    stockpile::{Good, Stockpile},
+    structure::{
+        registry::{build_structure_registry, StructureRegistry},
+        StructureId,
+    },
+};
 
-use super::build_button::{BuildButtonBundle, BuildButtonLabelBundle};
+use super::build_button::{BuildButtonBundle, BuildButtonColors, BuildButtonLabelBundle, Disabled};
 
 pub struct HudPlugin;
 
 impl Plugin for HudPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnExit(AppState::Loading), spawn_hud)
-            .add_systems(OnExit(AppState::InGame), despawn_hud);
+        app.add_systems(
+            OnExit(AppState::Loading),
+            spawn_hud.after(build_structure_registry),
+        )
+        .add_systems(OnExit(AppState::InGame), despawn_hud)
+        .add_systems(
+            Update,
+            (
+                update_stockpile_labels,
+                animate_stockpile_deltas,
+                disable_unaffordable_buttons,
+            )
+                .run_if(in_state(AppState::InGame)),
+        );
     }
 }
 
@@ -17,9 +36,36 @@ impl Plugin for HudPlugin {
 struct Hud;
 
 #[derive(Component, Clone)]
-pub struct BuildOnPress(pub StructureType);
+pub struct BuildOnPress(pub StructureId);
 
-fn spawn_hud(mut commands: Commands, assets: Res<AssetServer>) {
+/// Switches demolish mode on and off when pressed.
+#[derive(Component, Clone)]
+pub struct DemolishOnPress;
+
+/// Shows how much of a good there is in the stockpile.
+#[derive(Component)]
+struct StockpileLabel {
+    good: Good,
+    /// The amount currently shown, or `None` until the label is first updated.
+    amount: Option<u32>,
+}
+
+/// A change in the amount of a good, which floats up towards its label and
+/// fades out.
+#[derive(Component)]
+struct StockpileDelta(Timer);
+
+/// How long a stockpile delta is shown, in seconds.
+const STOCKPILE_DELTA_DURATION: f32 = 1.2;
+/// How far below the top of its label a stockpile delta appears, in pixels.
+const STOCKPILE_DELTA_START: f32 = 36.;
+/// How far a stockpile delta floats up before it's gone, in pixels.
+const STOCKPILE_DELTA_RISE: f32 = 16.;
+const STOCKPILE_GAIN_COLOR: Color = Color::rgb(0.4, 0.9, 0.4);
+const STOCKPILE_LOSS_COLOR: Color = Color::rgb(0.95, 0.35, 0.3);
+
+/// Spawns the HUD, with a build button for every structure in the registry.
+fn spawn_hud(mut commands: Commands, assets: Res<AssetServer>, registry: Res<StructureRegistry>) {
     let font = assets.load("fonts/AxeHandel.ttf");
     commands
         .spawn((
@@ -28,6 +74,7 @@ fn spawn_hud(mut commands: Commands, assets: Res<AssetServer>) {
                     width: Val::Px(100.),
                     height: Val::Percent(100.),
                     flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(10.),
                     padding: UiRect::all(Val::Px(10.)),
                     ..Default::default()
                 },
@@ -36,15 +83,159 @@ fn spawn_hud(mut commands: Commands, assets: Res<AssetServer>) {
             Hud,
         ))
         .with_children(|commands| {
+            for definition in registry.iter() {
+                commands
+                    .spawn((
+                        BuildButtonBundle::default(),
+                        BuildOnPress(definition.id.clone()),
+                    ))
+                    .with_children(|commands| match &definition.icon {
+                        Some(icon) => {
+                            commands.spawn(ImageBundle {
+                                style: Style {
+                                    width: Val::Percent(100.),
+                                    height: Val::Percent(100.),
+                                    ..default()
+                                },
+                                image: assets.load(icon).into(),
+                                ..default()
+                            });
+                        }
+                        None => {
+                            commands
+                                .spawn(BuildButtonLabelBundle::new(&definition.name, font.clone()));
+                        }
+                    });
+            }
+
             commands
                 .spawn((
-                    BuildButtonBundle::default(),
-                    BuildOnPress(StructureType::House),
+                    BuildButtonBundle::default().with_active_state(GameState::Demolishing),
+                    DemolishOnPress,
                 ))
                 .with_children(|commands| {
-                    commands.spawn(BuildButtonLabelBundle::new("House", font));
+                    commands.spawn(
+                        BuildButtonLabelBundle::new("Demolish", font.clone()).with_font_size(18.),
+                    );
                 });
         });
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(10.),
+                    right: Val::Px(10.),
+                    column_gap: Val::Px(20.),
+                    padding: UiRect::axes(Val::Px(12.), Val::Px(6.)),
+                    ..Default::default()
+                },
+                background_color: BuildButtonColors::BG.into(),
+                ..Default::default()
+            },
+            Hud,
+        ))
+        .with_children(|commands| {
+            for good in Good::ALL {
+                commands
+                    .spawn(NodeBundle::default())
+                    .with_children(|commands| {
+                        commands.spawn((
+                            TextBundle::from_section(
+                                "",
+                                TextStyle {
+                                    font: font.clone(),
+                                    font_size: 24.,
+                                    color: BuildButtonColors::FG,
+                                },
+                            ),
+                            StockpileLabel { good, amount: None },
+                        ));
+                    });
+            }
+        });
+}
+
+/// Keeps the resource bar up to date with the stockpile, showing how much each
+/// amount changed by.
+fn update_stockpile_labels(
+    mut commands: Commands,
+    mut label_query: Query<(&mut StockpileLabel, &mut Text, &Parent)>,
+    stockpile: Res<Stockpile>,
+) {
+    for (mut label, mut text, parent) in label_query.iter_mut() {
+        let amount = stockpile.goods().get(label.good);
+        if label.amount == Some(amount) {
+            continue;
+        }
+
+        if let Some(previous) = label.amount {
+            let (delta, color) = if amount > previous {
+                (format!("+{}", amount - previous), STOCKPILE_GAIN_COLOR)
+            } else {
+                (format!("-{}", previous - amount), STOCKPILE_LOSS_COLOR)
+            };
+            let style = TextStyle {
+                font_size: 20.,
+                color,
+                ..text.sections[0].style.clone()
+            };
+            commands.entity(parent.get()).with_children(|commands| {
+                commands.spawn((
+                    TextBundle::from_section(delta, style).with_style(Style {
+                        position_type: PositionType::Absolute,
+                        top: Val::Px(STOCKPILE_DELTA_START),
+                        right: Val::Px(0.),
+                        ..default()
+                    }),
+                    StockpileDelta(Timer::from_seconds(
+                        STOCKPILE_DELTA_DURATION,
+                        TimerMode::Once,
+                    )),
+                ));
+            });
+        }
+
+        label.amount = Some(amount);
+        text.sections[0].value = format!("{}: {amount}", label.good);
+    }
+}
+
+fn animate_stockpile_deltas(
+    mut commands: Commands,
+    mut delta_query: Query<(Entity, &mut StockpileDelta, &mut Style, &mut Text)>,
+    time: Res<Time>,
+) {
+    for (entity, mut delta, mut style, mut text) in delta_query.iter_mut() {
+        delta.0.tick(time.delta());
+        if delta.0.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        let progress = delta.0.percent();
+        style.top = Val::Px(STOCKPILE_DELTA_START - STOCKPILE_DELTA_RISE * progress);
+        for section in text.sections.iter_mut() {
+            section.style.color.set_a(1. - progress);
+        }
+    }
+}
+
+/// Grays out the build buttons of structures the stockpile can't afford.
+fn disable_unaffordable_buttons(
+    mut button_query: Query<(&BuildOnPress, &mut Disabled)>,
+    registry: Res<StructureRegistry>,
+    stockpile: Res<Stockpile>,
+) {
+    for (build_button, mut disabled) in button_query.iter_mut() {
+        let affordable = registry
+            .get(&build_button.0)
+            .is_some_and(|definition| stockpile.can_afford(&definition.cost));
+        if disabled.0 == affordable {
+            disabled.0 = !affordable;
+        }
+    }
 }
 
 fn despawn_hud(mut commands: Commands, hud_query: Query<Entity, With<Hud>>) {
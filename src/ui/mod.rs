@@ -1,5 +1,6 @@
 pub mod build_button;
 pub mod hud;
+pub mod placement_hint;
 
 use bevy::prelude::*;
 
@@ -7,6 +8,10 @@ pub struct UiPlugin;
 
 impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((build_button::BuildButtonPlugin, hud::HudPlugin));
+        app.add_plugins((
+            build_button::BuildButtonPlugin,
+            hud::HudPlugin,
+            placement_hint::PlacementHintPlugin,
+        ));
     }
 }
@@ -0,0 +1,84 @@
+use bevy::prelude::*;
+
+use crate::{
+    prelude::*,
+    state::GameState,
+    structure::planned_structure::{PlacementCheck, PlannedStructure},
+};
+
+pub struct PlacementHintPlugin;
+
+impl Plugin for PlacementHintPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnExit(AppState::Loading), spawn_placement_hint)
+            .add_systems(OnExit(AppState::InGame), despawn_placement_hint)
+            .add_systems(OnExit(GameState::Building), hide_placement_hint)
+            .add_systems(
+                Update,
+                update_placement_hint.run_if(in_state(GameState::Building)),
+            );
+    }
+}
+
+/// A label next to the mouse cursor explaining why the planned structure
+/// can't be placed where it is.
+#[derive(Component)]
+struct PlacementHint;
+
+/// How far from the mouse cursor the hint is shown, in logical pixels.
+const PLACEMENT_HINT_OFFSET: Vec2 = Vec2::new(18., 18.);
+
+fn spawn_placement_hint(mut commands: Commands, assets: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: assets.load("fonts/AxeHandel.ttf"),
+                font_size: 20.,
+                color: Color::rgb(1., 0.85, 0.8),
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            padding: UiRect::axes(Val::Px(6.), Val::Px(4.)),
+            ..default()
+        })
+        .with_background_color(Color::rgba(0.1, 0.05, 0.02, 0.8)),
+        Visibility::Hidden,
+        PlacementHint,
+    ));
+}
+
+fn despawn_placement_hint(mut commands: Commands, hint_query: Query<Entity, With<PlacementHint>>) {
+    for hint_entity in hint_query.iter() {
+        commands.entity(hint_entity).despawn_recursive();
+    }
+}
+
+fn hide_placement_hint(mut hint_query: Query<&mut Visibility, With<PlacementHint>>) {
+    for mut visibility in hint_query.iter_mut() {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+fn update_placement_hint(
+    mut hint_query: Query<(&mut Text, &mut Style, &mut Visibility), With<PlacementHint>>,
+    structure_query: Query<&PlacementCheck, With<PlannedStructure>>,
+    mouse_position: Res<MousePosition>,
+) {
+    let error = structure_query
+        .iter()
+        .find_map(|placement_check| placement_check.0.as_ref().err());
+
+    for (mut text, mut style, mut visibility) in hint_query.iter_mut() {
+        let Some(error) = error else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+
+        *visibility = Visibility::Visible;
+        text.sections[0].value = error.to_string();
+        style.left = Val::Px(mouse_position.viewport.x + PLACEMENT_HINT_OFFSET.x);
+        style.top = Val::Px(mouse_position.viewport.y + PLACEMENT_HINT_OFFSET.y);
+    }
+}